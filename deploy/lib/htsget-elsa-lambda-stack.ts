@@ -1,4 +1,4 @@
-import { Duration, Stack, StackProps, Tags } from "aws-cdk-lib";
+import { CfnOutput, Duration, Stack, StackProps, Tags } from "aws-cdk-lib";
 import { Construct } from "constructs";
 import * as iam from "aws-cdk-lib/aws-iam";
 import { RustFunction, Settings } from "rust.aws-cdk-lambda";
@@ -11,6 +11,8 @@ import { HttpJwtAuthorizer } from "@aws-cdk/aws-apigatewayv2-authorizers-alpha";
 import { ARecord, HostedZone, RecordTarget } from "aws-cdk-lib/aws-route53";
 import { ApiGatewayv2DomainProperties } from "aws-cdk-lib/aws-route53-targets";
 import { Certificate } from "aws-cdk-lib/aws-certificatemanager";
+import { Queue } from "aws-cdk-lib/aws-sqs";
+import { SqsEventSource } from "aws-cdk-lib/aws-lambda-event-sources";
 import * as fs from "fs";
 import * as TOML from "@iarna/toml";
 
@@ -20,6 +22,7 @@ import * as TOML from "@iarna/toml";
 export type Config = {
   environment: string;
   htsgetConfig: { [key: string]: any };
+  cacheLocation?: string;
   allowCredentials?: boolean;
   allowHeaders?: string[];
   allowMethods?: apigwv2.CorsHttpMethod[];
@@ -50,13 +53,15 @@ export class HtsgetElsaLambdaStack extends Stack {
 
     Tags.of(this).add("Stack", STACK_NAME);
 
+    const config = this.getConfig();
+
     const lambdaRole = new iam.Role(this, id + "Role", {
       assumedBy: new iam.ServicePrincipal("lambda.amazonaws.com"),
       description: "Lambda execution role for " + id,
     });
 
     const s3BucketPolicy = new iam.PolicyStatement({
-      actions: ["s3:List*", "s3:Get*", "s3:PutObject"],
+      actions: ["s3:List*", "s3:Get*"],
       resources: ["arn:aws:s3:::*"],
     });
 
@@ -67,15 +72,28 @@ export class HtsgetElsaLambdaStack extends Stack {
     );
     lambdaRole.addToPolicy(s3BucketPolicy);
 
+    // Objects are only written and deleted under the cache and revocation prefixes of the cache bucket.
+    if (config.cacheLocation !== undefined) {
+      lambdaRole.addToPolicy(
+        new iam.PolicyStatement({
+          actions: ["s3:PutObject", "s3:DeleteObject"],
+          resources: [
+            `arn:aws:s3:::${config.cacheLocation}/htsget-manifest-cache/*`,
+            `arn:aws:s3:::${config.cacheLocation}/htsget-manifest-revocations/*`,
+          ],
+        }),
+      );
+    }
+
     // Set the workspace directory of htsget.
     Settings.WORKSPACE_DIR = "../";
     // Don't build htsget packages other than htsget-elsa-lambda.
     Settings.BUILD_INDIVIDUALLY = true;
 
-    const config = this.getConfig();
     let htsgetLambda = new RustFunction(this, id + "Function", {
       // Build htsget-elsa-lambda only.
       package: "htsget-elsa-lambda",
+      bin: "htsget-elsa-lambda",
       target: "aarch64-unknown-linux-gnu",
 
       memorySize: 128,
@@ -94,6 +112,38 @@ export class HtsgetElsaLambdaStack extends Stack {
       role: lambdaRole,
     });
 
+    // The events function applies release change notifications from Elsa to the cache.
+    const eventsLambda = new RustFunction(this, id + "EventsFunction", {
+      package: "htsget-elsa-lambda",
+      bin: "htsget-elsa-events",
+      target: "aarch64-unknown-linux-gnu",
+
+      memorySize: 128,
+      timeout: Duration.seconds(28),
+      environment: {
+        ...config.htsgetConfig,
+        RUST_LOG: "info,htsget_elsa=trace,htsget_elsa_lambda=trace",
+      },
+      buildEnvironment: {
+        RUSTFLAGS: "-C target-cpu=neoverse-n1",
+        CARGO_PROFILE_RELEASE_LTO: "true",
+        CARGO_PROFILE_RELEASE_CODEGEN_UNITS: "1",
+      },
+      architecture: Architecture.ARM_64,
+      role: lambdaRole,
+    });
+
+    const eventsQueue = new Queue(this, id + "EventsQueue", {
+      // Must be at least the timeout of the events function.
+      visibilityTimeout: Duration.seconds(30),
+    });
+    eventsLambda.addEventSource(new SqsEventSource(eventsQueue));
+
+    new CfnOutput(this, "EventsQueueUrl", {
+      description: "The queue that Elsa sends release change events to.",
+      value: eventsQueue.queueUrl,
+    });
+
     const parameterStoreConfig = config.parameterStoreConfig;
     const httpIntegration = new HttpLambdaIntegration(
       id + "HtsgetElsaIntegration",
@@ -260,6 +310,7 @@ export class HtsgetElsaLambdaStack extends Stack {
     return {
       environment: env,
       htsgetConfig: HtsgetElsaLambdaStack.configToEnv(configToml),
+      cacheLocation: configToml.cache_location as string | undefined,
       allowCredentials:
         configToml.ticket_server_cors_allow_credentials as boolean,
       allowHeaders: HtsgetElsaLambdaStack.convertCors(
@@ -0,0 +1,475 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{decode, decode_header, DecodingKey, Validation};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::instrument;
+
+use htsget_elsa::Error::{DeserializeError, InvalidAuthentication, SystemError};
+use htsget_elsa::Result;
+
+use crate::passport::{PassportConfig, Passports};
+
+/// The location of a JSON Web Key Set.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JwksLocation {
+    /// A local JWKS file.
+    Path(PathBuf),
+    /// A URL which serves the JWKS.
+    Url(String),
+}
+
+/// Configuration for validating the bearer tokens of requests.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AuthConfig {
+    jwks: JwksLocation,
+    issuer: String,
+    audience: Vec<String>,
+    passport: Option<PassportConfig>,
+}
+
+impl AuthConfig {
+    /// Create a new auth config.
+    pub fn new(jwks: JwksLocation, issuer: String, audience: Vec<String>) -> Self {
+        Self {
+            jwks,
+            issuer,
+            audience,
+            passport: None,
+        }
+    }
+
+    /// Set the config used to authorize access to datasets using GA4GH passports.
+    pub fn with_passport(mut self, passport: Option<PassportConfig>) -> Self {
+        self.passport = passport;
+        self
+    }
+
+    /// Get the location of the JWKS.
+    pub fn jwks(&self) -> &JwksLocation {
+        &self.jwks
+    }
+
+    /// Get the expected issuer of tokens.
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    /// Get the accepted audiences of tokens.
+    pub fn audience(&self) -> &[String] {
+        &self.audience
+    }
+
+    /// Get the config used to authorize access to datasets using GA4GH passports.
+    pub fn passport(&self) -> Option<&PassportConfig> {
+        self.passport.as_ref()
+    }
+}
+
+/// The verified claims of a bearer token.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Claims {
+    iss: String,
+    sub: Option<String>,
+    #[serde(flatten)]
+    other: Map<String, Value>,
+}
+
+impl Claims {
+    /// Get the issuer of the token.
+    pub fn issuer(&self) -> &str {
+        &self.iss
+    }
+
+    /// Get the subject of the token.
+    pub fn subject(&self) -> Option<&str> {
+        self.sub.as_deref()
+    }
+
+    /// Get any other claim by name.
+    pub fn claim(&self, name: &str) -> Option<&Value> {
+        self.other.get(name)
+    }
+}
+
+/// The caller of a request, which has the `Authorization` header and any verified claims.
+#[derive(Debug, Clone, Default)]
+pub struct Caller {
+    authorization: Option<String>,
+    claims: Option<Claims>,
+    granted_datasets: Option<HashSet<String>>,
+}
+
+impl Caller {
+    /// Create a new caller.
+    pub fn new(authorization: Option<String>, claims: Option<Claims>) -> Self {
+        Self {
+            authorization,
+            claims,
+            granted_datasets: None,
+        }
+    }
+
+    /// Set the datasets granted to the caller by their passport.
+    pub fn with_granted_datasets(mut self, granted_datasets: Option<HashSet<String>>) -> Self {
+        self.granted_datasets = granted_datasets;
+        self
+    }
+
+    /// Get the value of the `Authorization` header.
+    pub fn authorization(&self) -> Option<&str> {
+        self.authorization.as_deref()
+    }
+
+    /// Get the verified claims, which are only present if authentication is configured.
+    pub fn claims(&self) -> Option<&Claims> {
+        self.claims.as_ref()
+    }
+
+    /// Check whether the caller is granted any of the datasets. This is always true if passports
+    /// are not configured, and always false if the release declares no datasets.
+    pub fn is_granted_any(&self, dataset_uris: &[String]) -> bool {
+        match &self.granted_datasets {
+            None => true,
+            Some(granted) => dataset_uris
+                .iter()
+                .any(|dataset_uri| granted.contains(dataset_uri)),
+        }
+    }
+}
+
+/// Validates bearer tokens against a JWKS. The JWKS is loaded once when the authenticator is created.
+#[derive(Debug, Clone)]
+pub struct Authenticator {
+    jwks: JwkSet,
+    issuer: String,
+    audience: Vec<String>,
+    passports: Option<Passports>,
+}
+
+impl Authenticator {
+    /// Create an authenticator by loading the JWKS from the config.
+    pub async fn from_config(config: &AuthConfig) -> Result<Self> {
+        let authenticator = Self::new(
+            load_jwks(config.jwks()).await?,
+            config.issuer().to_string(),
+            config.audience().to_vec(),
+        );
+
+        Ok(match config.passport() {
+            Some(passport) => authenticator.with_passports(Passports::from_config(passport).await?),
+            None => authenticator,
+        })
+    }
+
+    /// Create an authenticator from a JWKS.
+    pub fn new(jwks: JwkSet, issuer: String, audience: Vec<String>) -> Self {
+        Self {
+            jwks,
+            issuer,
+            audience,
+            passports: None,
+        }
+    }
+
+    /// Set the passports used to authorize access to datasets.
+    pub fn with_passports(mut self, passports: Passports) -> Self {
+        self.passports = Some(passports);
+        self
+    }
+
+    /// Get the datasets granted by the passport in the claims, if passports are configured.
+    pub fn granted_datasets(&self, claims: &Claims) -> Option<HashSet<String>> {
+        self.passports
+            .as_ref()
+            .map(|passports| passports.granted_datasets(claims))
+    }
+
+    /// Validate the signature, issuer, audience and expiry of the bearer token in the
+    /// `Authorization` header, and return its claims.
+    #[instrument(level = "debug", skip_all)]
+    pub fn authenticate(&self, authorization: Option<&str>) -> Result<Claims> {
+        let token = authorization
+            .and_then(|authorization| {
+                authorization
+                    .strip_prefix("Bearer ")
+                    .or_else(|| authorization.strip_prefix("bearer "))
+            })
+            .ok_or_else(|| InvalidAuthentication("missing bearer token".to_string()))?;
+
+        verify_token(
+            token,
+            &self.jwks,
+            &self.issuer,
+            Some(&self.audience),
+            &["exp", "iss", "aud"],
+        )
+    }
+}
+
+/// Load a JWKS from a local file or a URL.
+pub async fn load_jwks(location: &JwksLocation) -> Result<JwkSet> {
+    match location {
+        JwksLocation::Path(path) => {
+            let jwks = fs::read_to_string(path)
+                .map_err(|err| SystemError(format!("failed to read JWKS: {}", err)))?;
+            serde_json::from_str(&jwks).map_err(|err| DeserializeError(err.to_string()))
+        }
+        JwksLocation::Url(url) => reqwest::get(url)
+            .await
+            .map_err(|err| SystemError(format!("failed to fetch JWKS: {}", err)))?
+            .json()
+            .await
+            .map_err(|err| DeserializeError(err.to_string())),
+    }
+}
+
+/// Verify the signature of a token using a key from the JWKS, check its issuer, audience and
+/// expiry, and return its claims.
+pub fn verify_token<T: DeserializeOwned>(
+    token: &str,
+    jwks: &JwkSet,
+    issuer: &str,
+    audience: Option<&[String]>,
+    required_claims: &[&str],
+) -> Result<T> {
+    let header = decode_header(token).map_err(|err| InvalidAuthentication(err.to_string()))?;
+    let jwk = find_key(jwks, header.kid.as_deref())?;
+
+    if jwk
+        .common
+        .algorithm
+        .is_some_and(|algorithm| algorithm != header.alg)
+    {
+        return Err(InvalidAuthentication(
+            "token algorithm does not match key".to_string(),
+        ));
+    }
+
+    let key = DecodingKey::from_jwk(jwk).map_err(|err| InvalidAuthentication(err.to_string()))?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_issuer(&[issuer]);
+    // The audience is only checked when it is set.
+    if let Some(audience) = audience {
+        validation.set_audience(audience);
+    }
+    validation.set_required_spec_claims(required_claims);
+
+    decode::<T>(token, &key, &validation)
+        .map(|data| data.claims)
+        .map_err(|err| InvalidAuthentication(err.to_string()))
+}
+
+/// Find the key that signed a token. If the token has no key id, the JWKS must have one key.
+fn find_key<'a>(jwks: &'a JwkSet, kid: Option<&str>) -> Result<&'a Jwk> {
+    match kid {
+        Some(kid) => jwks.find(kid),
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    }
+    .ok_or_else(|| InvalidAuthentication("no matching key for token".to_string()))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use serde_json::{json, Value};
+    use tempfile::TempDir;
+
+    use htsget_elsa::Error::InvalidAuthentication;
+
+    use crate::auth::{AuthConfig, Authenticator, JwksLocation};
+
+    pub(crate) const ISSUER: &str = "https://issuer.example.org";
+    pub(crate) const AUDIENCE: &str = "htsget";
+
+    /// A locally generated Ed25519 key pair, with its JWKS written to a temporary directory.
+    pub(crate) struct TestKeys {
+        encoding_key: EncodingKey,
+        jwks_dir: TempDir,
+    }
+
+    impl TestKeys {
+        pub(crate) fn generate() -> Self {
+            let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+            let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+
+            let jwks = json!({
+                "keys": [{
+                    "kty": "OKP",
+                    "crv": "Ed25519",
+                    "alg": "EdDSA",
+                    "kid": "test",
+                    "x": URL_SAFE_NO_PAD.encode(key_pair.public_key().as_ref()),
+                }]
+            });
+
+            let jwks_dir = TempDir::new().unwrap();
+            fs::write(jwks_dir.path().join("jwks.json"), jwks.to_string()).unwrap();
+
+            Self {
+                encoding_key: EncodingKey::from_ed_der(pkcs8.as_ref()),
+                jwks_dir,
+            }
+        }
+
+        pub(crate) fn jwks_location(&self) -> JwksLocation {
+            JwksLocation::Path(self.jwks_dir.path().join("jwks.json"))
+        }
+
+        pub(crate) fn auth_config(&self) -> AuthConfig {
+            AuthConfig::new(
+                self.jwks_location(),
+                ISSUER.to_string(),
+                vec![AUDIENCE.to_string()],
+            )
+        }
+
+        pub(crate) async fn authenticator(&self) -> Authenticator {
+            Authenticator::from_config(&self.auth_config())
+                .await
+                .unwrap()
+        }
+
+        /// Sign the claims and return the token.
+        pub(crate) fn sign(&self, claims: Value) -> String {
+            let mut header = Header::new(Algorithm::EdDSA);
+            header.kid = Some("test".to_string());
+
+            encode(&header, &claims, &self.encoding_key).unwrap()
+        }
+
+        /// Sign the claims and return the value of an `Authorization` header.
+        pub(crate) fn bearer(&self, claims: Value) -> String {
+            format!("Bearer {}", self.sign(claims))
+        }
+
+        /// Valid claims for the subject, which expire in an hour.
+        pub(crate) fn claims(subject: &str) -> Value {
+            json!({
+                "iss": ISSUER,
+                "aud": AUDIENCE,
+                "sub": subject,
+                "exp": now() + 3600,
+            })
+        }
+    }
+
+    pub(crate) fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    #[tokio::test]
+    async fn authenticate_valid_token() {
+        let keys = TestKeys::generate();
+        let authenticator = keys.authenticator().await;
+
+        let claims = authenticator
+            .authenticate(Some(&keys.bearer(TestKeys::claims("user"))))
+            .unwrap();
+
+        assert_eq!(claims.issuer(), ISSUER);
+        assert_eq!(claims.subject(), Some("user"));
+        assert_eq!(claims.claim("aud"), Some(&json!(AUDIENCE)));
+    }
+
+    #[tokio::test]
+    async fn authenticate_missing_token() {
+        let keys = TestKeys::generate();
+        let authenticator = keys.authenticator().await;
+
+        assert!(matches!(
+            authenticator.authenticate(None),
+            Err(InvalidAuthentication(_))
+        ));
+        assert!(matches!(
+            authenticator.authenticate(Some("Basic dXNlcjpwYXNz")),
+            Err(InvalidAuthentication(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn authenticate_wrong_issuer() {
+        let keys = TestKeys::generate();
+        let authenticator = keys.authenticator().await;
+
+        let mut claims = TestKeys::claims("user");
+        claims["iss"] = json!("https://other.example.org");
+
+        assert!(matches!(
+            authenticator.authenticate(Some(&keys.bearer(claims))),
+            Err(InvalidAuthentication(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn authenticate_wrong_audience() {
+        let keys = TestKeys::generate();
+        let authenticator = keys.authenticator().await;
+
+        let mut claims = TestKeys::claims("user");
+        claims["aud"] = json!("other");
+
+        assert!(matches!(
+            authenticator.authenticate(Some(&keys.bearer(claims))),
+            Err(InvalidAuthentication(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn authenticate_expired_token() {
+        let keys = TestKeys::generate();
+        let authenticator = keys.authenticator().await;
+
+        let mut claims = TestKeys::claims("user");
+        claims["exp"] = json!(now() - 3600);
+
+        assert!(matches!(
+            authenticator.authenticate(Some(&keys.bearer(claims))),
+            Err(InvalidAuthentication(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn authenticate_wrong_key() {
+        let keys = TestKeys::generate();
+        let authenticator = keys.authenticator().await;
+
+        let other_keys = TestKeys::generate();
+
+        assert!(matches!(
+            authenticator.authenticate(Some(&other_keys.bearer(TestKeys::claims("user")))),
+            Err(InvalidAuthentication(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn authenticate_missing_expiry() {
+        let keys = TestKeys::generate();
+        let authenticator = keys.authenticator().await;
+
+        let mut claims = TestKeys::claims("user");
+        claims.as_object_mut().unwrap().remove("exp");
+
+        assert!(matches!(
+            authenticator.authenticate(Some(&keys.bearer(claims))),
+            Err(InvalidAuthentication(_))
+        ));
+    }
+}
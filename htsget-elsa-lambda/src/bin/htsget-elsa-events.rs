@@ -0,0 +1,18 @@
+use htsget_lambda::Config as HtsgetConfig;
+use lambda_http::Error;
+
+use htsget_elsa_lambda::config::Config;
+use htsget_elsa_lambda::events::handle_events;
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    if let Some(path) = HtsgetConfig::parse_args() {
+        let config = Config::try_from(path.as_path())?;
+
+        config.htsget_config().setup_tracing()?;
+
+        handle_events(config).await
+    } else {
+        Ok(())
+    }
+}
@@ -1,11 +1,18 @@
+use std::collections::HashMap;
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use htsget_config::config::parser::from_path;
 use htsget_config::config::Config as HtsGetConfig;
+use htsget_elsa::annotation::GeneAnnotations;
+use htsget_elsa::contig::ContigAliases;
+use htsget_elsa::elsa_endpoint::ResolverOptions;
+use htsget_elsa::service_auth::ServiceAuthConfig;
 use http::uri::Authority;
 use serde::{Deserialize, Serialize};
 
+use crate::auth::AuthConfig;
+
 /// Configuration for htsget-elsa. Includes the standard HtsGetConfig.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Config {
@@ -14,6 +21,21 @@ pub struct Config {
     #[serde(with = "http_serde::authority")]
     elsa_endpoint_authority: Authority,
     cache_location: Option<String>,
+    #[serde(default)]
+    contig_aliases: HashMap<String, Vec<String>>,
+    config_resolvers_prefix: Option<String>,
+    auth: Option<AuthConfig>,
+    elsa_service_auth: Option<ServiceAuthConfig>,
+    manifest_public_key: Option<String>,
+    #[serde(default)]
+    contig_lengths: HashMap<String, HashMap<String, u32>>,
+    #[serde(default)]
+    gene_annotations: HashMap<String, PathBuf>,
+    #[serde(default)]
+    gene_panels: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    gene_flank: u32,
+    default_assembly: Option<String>,
 }
 
 impl Config {
@@ -27,9 +49,82 @@ impl Config {
             htsget_config,
             elsa_endpoint_authority,
             cache_location,
+            contig_aliases: HashMap::new(),
+            config_resolvers_prefix: None,
+            auth: None,
+            elsa_service_auth: None,
+            manifest_public_key: None,
+            contig_lengths: HashMap::new(),
+            gene_annotations: HashMap::new(),
+            gene_panels: HashMap::new(),
+            gene_flank: 0,
+            default_assembly: None,
         }
     }
 
+    /// Set the contig alias table, which adds to the built-in contig aliases.
+    pub fn with_contig_aliases(mut self, contig_aliases: HashMap<String, Vec<String>>) -> Self {
+        self.contig_aliases = contig_aliases;
+        self
+    }
+
+    /// Set the path prefix under which the config resolvers are reachable.
+    pub fn with_config_resolvers_prefix(mut self, config_resolvers_prefix: Option<String>) -> Self {
+        self.config_resolvers_prefix = config_resolvers_prefix;
+        self
+    }
+
+    /// Set the config used to validate the bearer tokens of requests.
+    pub fn with_auth(mut self, auth: Option<AuthConfig>) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Set how htsget-elsa authenticates itself to Elsa.
+    pub fn with_elsa_service_auth(mut self, elsa_service_auth: Option<ServiceAuthConfig>) -> Self {
+        self.elsa_service_auth = elsa_service_auth;
+        self
+    }
+
+    /// Set the base64 encoded Ed25519 public key used to verify the signatures of manifests.
+    pub fn with_manifest_public_key(mut self, manifest_public_key: Option<String>) -> Self {
+        self.manifest_public_key = manifest_public_key;
+        self
+    }
+
+    /// Set the contig length tables, keyed by their assembly, which add to the built-in lengths.
+    pub fn with_contig_lengths(
+        mut self,
+        contig_lengths: HashMap<String, HashMap<String, u32>>,
+    ) -> Self {
+        self.contig_lengths = contig_lengths;
+        self
+    }
+
+    /// Set the paths of the BED or GTF gene annotation files, keyed by their assembly.
+    pub fn with_gene_annotations(mut self, gene_annotations: HashMap<String, PathBuf>) -> Self {
+        self.gene_annotations = gene_annotations;
+        self
+    }
+
+    /// Set the gene panels, which are named lists of gene symbols.
+    pub fn with_gene_panels(mut self, gene_panels: HashMap<String, Vec<String>>) -> Self {
+        self.gene_panels = gene_panels;
+        self
+    }
+
+    /// Set the number of bases that gene and panel restrictions are padded by on either side.
+    pub fn with_gene_flank(mut self, gene_flank: u32) -> Self {
+        self.gene_flank = gene_flank;
+        self
+    }
+
+    /// Set the assembly of files which do not have an assembly in the manifest.
+    pub fn with_default_assembly(mut self, default_assembly: Option<String>) -> Self {
+        self.default_assembly = default_assembly;
+        self
+    }
+
     /// Get the standard htsget config.
     pub fn htsget_config(&self) -> &HtsGetConfig {
         &self.htsget_config
@@ -44,6 +139,72 @@ impl Config {
     pub fn cache_location(&self) -> Option<&str> {
         self.cache_location.as_deref()
     }
+
+    /// Get the path prefix under which the config resolvers are reachable.
+    pub fn config_resolvers_prefix(&self) -> Option<&str> {
+        self.config_resolvers_prefix.as_deref()
+    }
+
+    /// Get the config used to validate the bearer tokens of requests.
+    pub fn auth(&self) -> Option<&AuthConfig> {
+        self.auth.as_ref()
+    }
+
+    /// Get how htsget-elsa authenticates itself to Elsa.
+    pub fn elsa_service_auth(&self) -> Option<&ServiceAuthConfig> {
+        self.elsa_service_auth.as_ref()
+    }
+
+    /// Get the base64 encoded Ed25519 public key used to verify the signatures of manifests.
+    pub fn manifest_public_key(&self) -> Option<&str> {
+        self.manifest_public_key.as_deref()
+    }
+
+    /// Get the contig aliases, which include the built-in aliases and the configured alias table.
+    pub fn contig_aliases(&self) -> ContigAliases {
+        ContigAliases::default().with_alias_table(self.contig_aliases.clone())
+    }
+
+    /// Get the paths of the gene annotation files, keyed by their assembly.
+    pub fn gene_annotations(&self) -> &HashMap<String, PathBuf> {
+        &self.gene_annotations
+    }
+
+    /// Get the assembly of files which do not have an assembly in the manifest.
+    pub fn default_assembly(&self) -> Option<&str> {
+        self.default_assembly.as_deref()
+    }
+
+    /// Get the options used to convert Elsa manifests into resolvers. This reads the gene annotation
+    /// files, so it should only be called once.
+    pub fn resolver_options(&self) -> htsget_elsa::Result<ResolverOptions> {
+        let options = self.contig_lengths.iter().fold(
+            ResolverOptions::default()
+                .with_contig_aliases(self.contig_aliases())
+                .with_gene_panels(self.gene_panels.clone())
+                .with_gene_flank(self.gene_flank),
+            |options, (assembly, table)| {
+                let contig_lengths = options
+                    .contig_lengths(assembly)
+                    .cloned()
+                    .unwrap_or_default()
+                    .with_length_table(table.clone());
+
+                options.with_contig_lengths(assembly, contig_lengths)
+            },
+        );
+
+        let options = match self.default_assembly() {
+            Some(default_assembly) => options.with_default_assembly(default_assembly),
+            None => options,
+        };
+
+        self.gene_annotations
+            .iter()
+            .try_fold(options, |options, (assembly, path)| {
+                Ok(options.with_gene_annotations(assembly, GeneAnnotations::from_path(path)?))
+            })
+    }
 }
 
 impl TryFrom<&Path> for Config {
@@ -53,3 +214,45 @@ impl TryFrom<&Path> for Config {
         from_path(path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use crate::config::Config;
+
+    #[test]
+    fn config_resolvers_prefix() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("config.toml");
+
+        fs::write(
+            &path,
+            r#"elsa_endpoint_authority = "elsa-data.dev.umccr.org""#,
+        )
+        .unwrap();
+        assert_eq!(
+            Config::try_from(path.as_path())
+                .unwrap()
+                .config_resolvers_prefix(),
+            None
+        );
+
+        fs::write(
+            &path,
+            r#"
+                elsa_endpoint_authority = "elsa-data.dev.umccr.org"
+                config_resolvers_prefix = "public"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            Config::try_from(path.as_path())
+                .unwrap()
+                .config_resolvers_prefix(),
+            Some("public")
+        );
+    }
+}
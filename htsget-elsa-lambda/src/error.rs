@@ -0,0 +1,136 @@
+use http::{header, Response, StatusCode};
+use lambda_http::Body;
+use serde_json::json;
+
+use htsget_elsa::Error;
+
+/// Get the htsget error name and status code that corresponds to an htsget-elsa error.
+pub fn htsget_error(err: &Error) -> (&'static str, StatusCode) {
+    match err {
+        Error::InvalidReleaseUri(_)
+        | Error::InvalidIdentifier(_)
+        | Error::AmbiguousIdentifier(_) => ("InvalidInput", StatusCode::BAD_REQUEST),
+        Error::InvalidAuthentication(_) => ("InvalidAuthentication", StatusCode::UNAUTHORIZED),
+        Error::PermissionDenied(_) | Error::ReleaseNotActive(_) => {
+            ("PermissionDenied", StatusCode::FORBIDDEN)
+        }
+        Error::ReleaseNotFound(_) => ("NotFound", StatusCode::NOT_FOUND),
+        _ => ("InternalError", StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// Create an htsget error response from an htsget-elsa error.
+pub fn error_response(err: &Error) -> http::Result<Response<Body>> {
+    let (error, status) = htsget_error(err);
+
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            json!({
+                "htsget": {
+                    "error": error,
+                    "message": err.to_string(),
+                }
+            })
+            .to_string(),
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use http::StatusCode;
+    use lambda_http::Body;
+    use serde_json::{from_slice, json, Value};
+
+    use htsget_elsa::Error::{
+        AmbiguousIdentifier, GetManifest, InvalidAuthentication, InvalidIdentifier,
+        InvalidManifest, PermissionDenied, ReleaseNotActive, ReleaseNotFound,
+    };
+
+    use crate::error::error_response;
+
+    #[test]
+    fn release_not_found_response() {
+        let response = error_response(&ReleaseNotFound("R005".to_string())).unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            body_json(response.body()),
+            json!({
+                "htsget": {
+                    "error": "NotFound",
+                    "message": "release not found in Elsa: `R005`"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn permission_denied_response() {
+        for err in [
+            PermissionDenied("R004".to_string()),
+            ReleaseNotActive("R004".to_string()),
+        ] {
+            let response = error_response(&err).unwrap();
+
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            assert_eq!(
+                body_json(response.body())["htsget"]["error"],
+                "PermissionDenied"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_input_response() {
+        for err in [
+            InvalidIdentifier("R004/../HG00097".to_string()),
+            AmbiguousIdentifier("HG00097 identifies A, B".to_string()),
+        ] {
+            let response = error_response(&err).unwrap();
+
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            assert_eq!(
+                body_json(response.body())["htsget"]["error"],
+                "InvalidInput"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_authentication_response() {
+        let response =
+            error_response(&InvalidAuthentication("missing bearer token".to_string())).unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            body_json(response.body())["htsget"]["error"],
+            "InvalidAuthentication"
+        );
+    }
+
+    #[test]
+    fn internal_error_response() {
+        for err in [
+            GetManifest("503 Service Unavailable".to_string()),
+            InvalidManifest("bucket or key is empty".to_string()),
+        ] {
+            let response = error_response(&err).unwrap();
+
+            assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+            assert_eq!(
+                body_json(response.body())["htsget"]["error"],
+                "InternalError"
+            );
+            assert_eq!(
+                body_json(response.body())["htsget"]["message"],
+                err.to_string()
+            );
+        }
+    }
+
+    fn body_json(body: &Body) -> Value {
+        from_slice(body.as_ref()).unwrap()
+    }
+}
@@ -0,0 +1,254 @@
+use lambda_http::lambda_runtime::{self, service_fn, LambdaEvent};
+use lambda_http::Error;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{info, instrument};
+
+use htsget_elsa::elsa_endpoint::ElsaEndpoint;
+use htsget_elsa::s3::S3;
+use htsget_elsa::Error::DeserializeError;
+use htsget_elsa::Result;
+
+use crate::config::Config;
+
+/// How the sharing configuration of a release changed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ReleaseAction {
+    /// The sharing configuration changed, so the cached resolvers are stale.
+    Changed,
+    /// The release was revoked, so it must be refused immediately.
+    Revoked,
+    /// A revoked release was reinstated.
+    Reinstated,
+}
+
+/// A notification from Elsa that a release changed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseEvent {
+    release_key: String,
+    action: ReleaseAction,
+}
+
+impl ReleaseEvent {
+    /// Create a new release event.
+    pub fn new(release_key: String, action: ReleaseAction) -> Self {
+        Self {
+            release_key,
+            action,
+        }
+    }
+
+    /// Get the release key.
+    pub fn release_key(&self) -> &str {
+        &self.release_key
+    }
+
+    /// Get how the release changed.
+    pub fn action(&self) -> ReleaseAction {
+        self.action
+    }
+}
+
+/// An SQS record, which has an EventBridge event or a release event as its body.
+#[derive(Deserialize, Debug)]
+struct SqsRecord {
+    body: String,
+}
+
+/// The shapes of events that can contain release events.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum EventEnvelope {
+    Sqs {
+        #[serde(rename = "Records")]
+        records: Vec<SqsRecord>,
+    },
+    EventBridge {
+        detail: ReleaseEvent,
+    },
+    Release(ReleaseEvent),
+}
+
+/// Get the release events from an EventBridge event, a batch of SQS records or a bare release
+/// event.
+pub fn release_events(event: Value) -> Result<Vec<ReleaseEvent>> {
+    let envelope: EventEnvelope =
+        serde_json::from_value(event).map_err(|err| DeserializeError(err.to_string()))?;
+
+    match envelope {
+        EventEnvelope::Sqs { records } => records
+            .iter()
+            .map(|record| {
+                serde_json::from_str(&record.body)
+                    .map_err(|err| DeserializeError(err.to_string()))
+                    .and_then(release_events)
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(|events| events.into_iter().flatten().collect()),
+        EventEnvelope::EventBridge { detail } => Ok(vec![detail]),
+        EventEnvelope::Release(event) => Ok(vec![event]),
+    }
+}
+
+/// Apply a release event to the cache. Changed releases have their cached resolvers purged, and are
+/// fetched from Elsa again on the next htsget request, using the authorization of that request.
+#[instrument(level = "debug", skip(elsa_endpoint))]
+pub async fn handle_release_event<'a>(
+    elsa_endpoint: &ElsaEndpoint<'a, S3, S3>,
+    event: &ReleaseEvent,
+) -> Result<()> {
+    let release_key = event.release_key();
+
+    match event.action() {
+        ReleaseAction::Changed => elsa_endpoint.purge(release_key).await,
+        ReleaseAction::Revoked => elsa_endpoint.revoke(release_key).await,
+        ReleaseAction::Reinstated => {
+            elsa_endpoint.reinstate(release_key).await?;
+            elsa_endpoint.purge(release_key).await
+        }
+    }
+}
+
+/// The event handler, which applies release change notifications from Elsa to the cache. Events
+/// only change the cache, so the endpoint does not authenticate to Elsa, verify manifests or
+/// convert them into resolvers.
+pub async fn handle_events(config: Config) -> std::result::Result<(), Error> {
+    let s3 = S3::new_with_default_config(config.cache_location().map(str::to_string)).await;
+    let elsa_endpoint = ElsaEndpoint::new(config.elsa_endpoint_authority().clone(), &s3, &s3)?;
+
+    lambda_runtime::run(service_fn(|event: LambdaEvent<Value>| async {
+        info!(event = ?event.payload, "received event");
+
+        for release_event in release_events(event.payload)? {
+            handle_release_event(&elsa_endpoint, &release_event).await?;
+        }
+
+        Ok::<_, Error>(())
+    }))
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    use http::uri::Authority;
+    use serde_json::{json, Value};
+
+    use htsget_elsa::elsa_endpoint::{cache_key, ElsaEndpoint};
+    use htsget_elsa::s3::S3;
+    use htsget_elsa::test_utils::with_test_mocks;
+    use htsget_elsa::Error::{DeserializeError, PermissionDenied};
+    use htsget_elsa::{Cache, ReleaseResolvers, ResolversFromElsa};
+
+    use crate::events::{handle_release_event, release_events, ReleaseAction, ReleaseEvent};
+
+    #[test]
+    fn release_events_eventbridge() {
+        assert_eq!(
+            release_events(event("data/events/event_release_changed.json")).unwrap(),
+            vec![ReleaseEvent::new(
+                "R004".to_string(),
+                ReleaseAction::Changed
+            )]
+        );
+    }
+
+    #[test]
+    fn release_events_sqs() {
+        assert_eq!(
+            release_events(event("data/events/event_release_sqs.json")).unwrap(),
+            vec![
+                ReleaseEvent::new("R004".to_string(), ReleaseAction::Revoked),
+                ReleaseEvent::new("R005".to_string(), ReleaseAction::Reinstated),
+            ]
+        );
+    }
+
+    #[test]
+    fn release_events_invalid() {
+        assert!(matches!(
+            release_events(json!({ "releaseKey": "R004", "action": "deleted" })),
+            Err(DeserializeError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn release_changed_purges_cache() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    Authority::from_str(&endpoint).unwrap(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                s3.put(cache_key("R004", None), ReleaseResolvers::default(), 1000)
+                    .await
+                    .unwrap();
+
+                for event in
+                    release_events(event("data/events/event_release_changed.json")).unwrap()
+                {
+                    handle_release_event(&endpoint, &event).await.unwrap();
+                }
+
+                assert!(s3.get(cache_key("R004", None)).await.unwrap().is_none());
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn release_revoked_and_reinstated() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    Authority::from_str(&endpoint).unwrap(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                endpoint.try_get("R004".to_string(), None).await.unwrap();
+
+                let revoked = ReleaseEvent::new("R004".to_string(), ReleaseAction::Revoked);
+                handle_release_event(&endpoint, &revoked).await.unwrap();
+
+                assert!(s3.get(cache_key("R004", None)).await.unwrap().is_none());
+                assert!(matches!(
+                    endpoint.try_get("R004".to_string(), None).await,
+                    Err(PermissionDenied(_))
+                ));
+
+                let reinstated = ReleaseEvent::new("R004".to_string(), ReleaseAction::Reinstated);
+                handle_release_event(&endpoint, &reinstated).await.unwrap();
+
+                assert!(s3.get(cache_key("R004", None)).await.unwrap().is_none());
+                assert!(endpoint.try_get("R004".to_string(), None).await.is_ok());
+            },
+            2,
+        )
+        .await;
+    }
+
+    fn event(path: &str) -> Value {
+        let path = PathBuf::from_str(env!("CARGO_MANIFEST_DIR"))
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join(path);
+
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+}
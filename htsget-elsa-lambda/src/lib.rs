@@ -1,22 +1,49 @@
 use std::sync::Arc;
 
 use htsget_config::resolver::Resolver;
+use htsget_http::Endpoint;
 use htsget_lambda::RouteType::Id;
 use htsget_lambda::{handle_request_service_fn, Route, Router};
-use http::{Response, StatusCode};
+use http::header::AUTHORIZATION;
+use http::Response;
 use lambda_http::{Body, Error, Request};
-use tracing::{info, instrument, warn};
+use tracing::{debug, info, instrument, warn};
 
-use htsget_elsa::elsa_endpoint::ElsaEndpoint;
+use htsget_elsa::elsa_endpoint::{ElsaEndpoint, ResolverOptions};
 use htsget_elsa::s3::S3;
-use htsget_elsa::{Cache, GetObject, ResolversFromElsa};
+use htsget_elsa::service_auth::ServiceAuth;
+use htsget_elsa::signature::ManifestVerifier;
+use htsget_elsa::Error::{AmbiguousIdentifier, PermissionDenied};
+use htsget_elsa::{Cache, GetObject, ReleaseResolvers, ResolversFromElsa};
 
+use crate::auth::{Authenticator, Caller};
 use crate::config::Config;
+use crate::error::error_response;
 
+pub mod auth;
 pub mod config;
+pub mod error;
+pub mod events;
+pub mod passport;
 
 /// The request handler.
 pub async fn handle_request(config: Config) -> Result<(), Error> {
+    let authenticator = match config.auth() {
+        Some(auth) => Some(Authenticator::from_config(auth).await?),
+        None => None,
+    };
+    let authenticator = authenticator.as_ref();
+    let service_auth = config
+        .elsa_service_auth()
+        .map(ServiceAuth::from_config)
+        .transpose()?;
+    let service_auth = service_auth.as_ref();
+    let manifest_verifier = config
+        .manifest_public_key()
+        .map(ManifestVerifier::from_base64)
+        .transpose()?;
+    let resolver_options = config.resolver_options()?;
+
     handle_request_service_fn(
         config.htsget_config().ticket_server().cors().clone(),
         |event: Request| async {
@@ -27,18 +54,18 @@ pub async fn handle_request(config: Config) -> Result<(), Error> {
                     let s3 =
                         S3::new_with_default_config(config.cache_location().map(str::to_string))
                             .await;
-                    let elsa_endpoint =
-                        match ElsaEndpoint::new(config.elsa_endpoint_authority().clone(), &s3, &s3)
-                        {
-                            Ok(elsa_endpoint) => elsa_endpoint,
-                            Err(err) => {
-                                return Response::builder()
-                                    .status(StatusCode::INTERNAL_SERVER_ERROR)
-                                    .body(Body::from(err.to_string()));
-                            }
-                        };
-
-                    route_request(&config, event, route, &elsa_endpoint).await
+                    let elsa_endpoint = match elsa_endpoint(
+                        &config,
+                        &s3,
+                        service_auth,
+                        manifest_verifier.clone(),
+                        resolver_options.clone(),
+                    ) {
+                        Ok(elsa_endpoint) => elsa_endpoint,
+                        Err(err) => return error_response(&err),
+                    };
+
+                    route_request(&config, event, route, authenticator, &elsa_endpoint).await
                 }
                 Err(err) => err,
             }
@@ -47,50 +74,174 @@ pub async fn handle_request(config: Config) -> Result<(), Error> {
     .await
 }
 
-/// Route the request with the Elsa endpoint.
+/// Create the Elsa endpoint from the config, using the S3 storage as the cache.
+pub fn elsa_endpoint<'a>(
+    config: &Config,
+    s3: &'a S3,
+    service_auth: Option<&'a ServiceAuth>,
+    manifest_verifier: Option<ManifestVerifier>,
+    resolver_options: ResolverOptions,
+) -> htsget_elsa::Result<ElsaEndpoint<'a, S3, S3>> {
+    Ok(
+        ElsaEndpoint::new(config.elsa_endpoint_authority().clone(), s3, s3)?
+            .with_resolver_options(resolver_options)
+            .with_service_auth(service_auth)
+            .with_manifest_verifier(manifest_verifier),
+    )
+}
+
+/// Route the request with the Elsa endpoint. If an authenticator is present, the request is
+/// authenticated before any resolvers are fetched.
 pub async fn route_request<'a>(
     config: &Config,
     event: Request,
     route: Route,
+    authenticator: Option<&Authenticator>,
     elsa_endpoint: &ElsaEndpoint<'a, S3, S3>,
 ) -> http::Result<Response<Body>> {
-    let resolver = get_resolvers(config, &route, elsa_endpoint).await?;
-    let router = Router::new(Arc::new(resolver), config.htsget_config().service_info());
+    let caller = match authenticate(authenticator, &event) {
+        Ok(caller) => caller,
+        Err(err) => return error_response(&err),
+    };
+    let resolvers = match get_resolvers(config, &route, &caller, elsa_endpoint).await {
+        Ok(resolvers) => resolvers,
+        Err(err) => return error_response(&err),
+    };
+    let router = Router::new(Arc::new(resolvers), config.htsget_config().service_info());
 
     router.route_request_with_route(event, route).await
 }
 
-/// Get the resolvers for this route. First tries htsget-elsa, then falls back to the
-/// standard htsget-rs resolvers.
-#[instrument(level = "debug", skip(elsa_endpoint), ret)]
+/// Get the value of the `Authorization` header of the request, which is forwarded to Elsa.
+pub fn authorization(event: &Request) -> Option<String> {
+    event
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+/// Get the caller of the request, validating its bearer token if an authenticator is present.
+pub fn authenticate(
+    authenticator: Option<&Authenticator>,
+    event: &Request,
+) -> htsget_elsa::Result<Caller> {
+    let authorization = authorization(event);
+    let claims = authenticator
+        .map(|authenticator| authenticator.authenticate(authorization.as_deref()))
+        .transpose()?;
+    let granted_datasets = authenticator
+        .zip(claims.as_ref())
+        .and_then(|(authenticator, claims)| authenticator.granted_datasets(claims));
+
+    Ok(Caller::new(authorization, claims).with_granted_datasets(granted_datasets))
+}
+
+/// Get the resolvers for this route. Elsa and config resolvers are kept in separate namespaces, so
+/// that a file restricted by Elsa cannot be queried through a config resolver:
+/// * If a `config_resolvers_prefix` is set, ids under that prefix only use the config resolvers.
+/// * All other ids only use resolvers from Elsa, and if that fails the error is returned. They never
+///   fall back to the config resolvers, which could otherwise serve a restricted file using its raw
+///   bucket path.
+///
+/// The caller's authorization is forwarded to Elsa, so that only releases which the caller is a
+/// member of are returned. If passports are configured, the caller must also be granted one of the
+/// datasets that the release declares.
+#[instrument(level = "debug", skip(caller, elsa_endpoint), ret)]
 pub async fn get_resolvers<'a, C, S>(
     config: &Config,
     route: &Route,
+    caller: &Caller,
     elsa_endpoint: &'a ElsaEndpoint<'a, C, S>,
-) -> http::Result<Vec<Resolver>>
+) -> htsget_elsa::Result<Vec<Resolver>>
 where
-    C: Cache<Item = Vec<Resolver>, Error = htsget_elsa::Error> + Send + Sync,
+    C: Cache<Item = ReleaseResolvers, Error = htsget_elsa::Error> + Send + Sync,
     S: GetObject<Error = htsget_elsa::Error> + Send + Sync,
 {
     if let Id(id) = route.route_type() {
-        if let Some(release_key) = id.split('/').collect::<Vec<&str>>().first() {
-            if let Ok(mut resolvers) = elsa_endpoint.try_get(release_key.to_string()).await {
-                resolvers.append(&mut config.htsget_config().resolvers().to_vec());
+        let prefix = config.config_resolvers_prefix();
+        if let Some(prefix) = prefix {
+            if id
+                .strip_prefix(prefix)
+                .is_some_and(|id| id.starts_with('/'))
+            {
+                return Ok(config.htsget_config().resolvers().to_vec());
+            }
+        }
 
-                return Ok(resolvers);
+        let release_key = id.split('/').next().unwrap_or_default();
+        if let Some(subject) = caller.claims().and_then(|claims| claims.subject()) {
+            debug!(
+                subject,
+                release_key, "getting resolvers for authenticated caller"
+            );
+        }
+
+        match elsa_endpoint
+            .try_get(
+                release_key.to_string(),
+                caller.authorization().map(str::to_string),
+            )
+            .await
+        {
+            Ok(release) if !caller.is_granted_any(release.dataset_uris()) => {
+                warn!(
+                    release_key,
+                    "caller is not granted any of the datasets of the release"
+                );
+
+                return Err(PermissionDenied(release_key.to_string()));
+            }
+            Ok(release) => {
+                let specimen_id = id
+                    .strip_prefix(release_key)
+                    .and_then(|id| id.strip_prefix('/'))
+                    .unwrap_or_default();
+                let refused = match route.endpoint() {
+                    Endpoint::Reads => release.is_refused_read(specimen_id),
+                    Endpoint::Variants => release.is_refused_variant(specimen_id),
+                };
+                if refused {
+                    warn!(
+                        release_key,
+                        specimen_id, "file is refused rather than shared"
+                    );
+
+                    return Err(PermissionDenied(id.to_string()));
+                }
+
+                let ambiguous = match route.endpoint() {
+                    Endpoint::Reads => release.ambiguous_reads(specimen_id),
+                    Endpoint::Variants => release.ambiguous_variants(specimen_id),
+                };
+                if let Some(files) = ambiguous {
+                    warn!(
+                        release_key,
+                        specimen_id, "specimen id identifies more than one file"
+                    );
+
+                    return Err(AmbiguousIdentifier(format!(
+                        "{specimen_id} identifies {}",
+                        files.iter().cloned().collect::<Vec<_>>().join(", ")
+                    )));
+                }
+
+                return Ok(release.into_resolvers());
+            }
+            Err(err) => {
+                warn!(err = %err, "failed to get resolvers from elsa endpoint");
+
+                return Err(err);
             }
         }
     }
 
-    warn!(
-        "failed to get resolvers from elsa endpoint, attempting to use only resolvers from config"
-    );
-
     Ok(config.htsget_config().resolvers().to_vec())
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
     use std::fs;
     use std::path::PathBuf;
     use std::str::FromStr;
@@ -98,18 +249,123 @@ mod tests {
     use htsget_config::resolver::Resolver;
     use htsget_lambda::Route;
     use htsget_test::http_tests::default_test_config;
+    use http::header::AUTHORIZATION;
     use http::uri::Authority;
+    use http::{HeaderValue, StatusCode};
     use lambda_http::request::from_str;
+    use lambda_http::Request;
 
     use htsget_elsa::elsa_endpoint::ElsaEndpoint;
     use htsget_elsa::s3::S3;
-    use htsget_elsa::test_utils::{is_manifest_resolvers, with_test_mocks};
+    use htsget_elsa::test_utils::{
+        example_elsa_manifest, is_manifest_resolvers, with_manifest_mocks, with_test_mocks,
+        AUTHORIZED_RELEASE, EXAMPLE_DATASET_URI, MEMBER_AUTHORIZATION,
+    };
+    use htsget_elsa::Error::{AmbiguousIdentifier, PermissionDenied, ReleaseNotFound};
 
+    use crate::auth::tests::TestKeys;
+    use crate::auth::Caller;
     use crate::config::Config;
-    use crate::get_resolvers;
+    use crate::{authenticate, authorization, get_resolvers, route_request};
+
+    /// A request for a file using its raw bucket path, which is also matched by a config resolver.
+    const BUCKET_PATH: &str = "/reads/umccr-10g-data-dev/HG00097/HG00097.bam";
 
     #[tokio::test]
     async fn test_route_request() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let config = Config::new(
+                    default_test_config(),
+                    Authority::from_str(&endpoint).unwrap(),
+                    Some("cache".to_string()),
+                )
+                .with_config_resolvers_prefix(Some("public".to_string()));
+
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    config.elsa_endpoint_authority().clone(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                let response = resolvers_for_path(
+                    &config,
+                    &endpoint,
+                    "/variants/public/1-vcf/sample1-bcbio-cancer",
+                    None,
+                )
+                .await;
+
+                assert!(!is_manifest_resolvers(response.unwrap()));
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn route_request_bucket_path_no_fallback() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let config = Config::new(
+                    default_test_config(),
+                    Authority::from_str(&endpoint).unwrap(),
+                    Some("cache".to_string()),
+                );
+
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    config.elsa_endpoint_authority().clone(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                let response = resolvers_for_path(&config, &endpoint, BUCKET_PATH, None).await;
+
+                assert!(matches!(response, Err(ReleaseNotFound(_))));
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn route_request_elsa_endpoint() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let config = Config::new(
+                    default_test_config(),
+                    Authority::from_str(&endpoint).unwrap(),
+                    Some("cache".to_string()),
+                );
+
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    config.elsa_endpoint_authority().clone(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                let response =
+                    resolvers_from_endpoint(&config, &endpoint, "data/events/event_elsa.json")
+                        .await;
+
+                assert!(is_manifest_resolvers(response.unwrap()));
+            },
+            1,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn route_request_fail_closed() {
         with_test_mocks(
             |endpoint, s3_client, reqwest_client, _| async move {
                 let config = Config::new(
@@ -130,6 +386,102 @@ mod tests {
                 let response =
                     resolvers_from_endpoint(&config, &endpoint, "data/events/event_get.json").await;
 
+                assert!(
+                    matches!(response, Err(ReleaseNotFound(release_key)) if release_key == "1-vcf")
+                );
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn route_request_bucket_path_fail_closed() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let config = Config::new(
+                    default_test_config(),
+                    Authority::from_str(&endpoint).unwrap(),
+                    Some("cache".to_string()),
+                );
+
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    config.elsa_endpoint_authority().clone(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                let response = resolvers_for_path(&config, &endpoint, BUCKET_PATH, None).await;
+
+                assert!(matches!(response, Err(ReleaseNotFound(_))));
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn route_request_bucket_path_with_prefix() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let config = Config::new(
+                    default_test_config(),
+                    Authority::from_str(&endpoint).unwrap(),
+                    Some("cache".to_string()),
+                )
+                .with_config_resolvers_prefix(Some("public".to_string()));
+
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    config.elsa_endpoint_authority().clone(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                let response = resolvers_for_path(&config, &endpoint, BUCKET_PATH, None).await;
+
+                assert!(matches!(response, Err(ReleaseNotFound(_))));
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn route_request_config_prefix() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let config = Config::new(
+                    default_test_config(),
+                    Authority::from_str(&endpoint).unwrap(),
+                    Some("cache".to_string()),
+                )
+                .with_config_resolvers_prefix(Some("public".to_string()));
+
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    config.elsa_endpoint_authority().clone(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                let response = resolvers_for_path(
+                    &config,
+                    &endpoint,
+                    &format!("/reads/public{}", BUCKET_PATH.trim_start_matches("/reads")),
+                    None,
+                )
+                .await
+                .unwrap();
+
+                assert_eq!(response.len(), config.htsget_config().resolvers().len());
                 assert!(!is_manifest_resolvers(response));
             },
             0,
@@ -138,7 +490,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn route_request_elsa_endpoint() {
+    async fn route_request_elsa_without_config_resolvers() {
         with_test_mocks(
             |endpoint, s3_client, reqwest_client, _| async move {
                 let config = Config::new(
@@ -158,30 +510,420 @@ mod tests {
 
                 let response =
                     resolvers_from_endpoint(&config, &endpoint, "data/events/event_elsa.json")
+                        .await
+                        .unwrap();
+
+                assert!(response
+                    .iter()
+                    .all(|resolver| resolver.regex().to_string().starts_with("^R004/")));
+            },
+            1,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn route_request_forwards_authorization() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let config = Config::new(
+                    default_test_config(),
+                    Authority::from_str(&endpoint).unwrap(),
+                    Some("cache".to_string()),
+                );
+
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    config.elsa_endpoint_authority().clone(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                let path = format!("/reads/{AUTHORIZED_RELEASE}/30F9F3FED8F711ED8C35DBEF59E9F537");
+
+                let response = resolvers_for_path(
+                    &config,
+                    &endpoint,
+                    &path,
+                    Some(MEMBER_AUTHORIZATION.to_string()),
+                )
+                .await;
+                assert!(is_manifest_resolvers(response.unwrap()));
+
+                let response =
+                    resolvers_for_path(&config, &endpoint, &path, Some("Bearer other".to_string()))
                         .await;
+                assert!(matches!(response, Err(PermissionDenied(_))));
 
-                assert!(is_manifest_resolvers(response));
+                let response = resolvers_for_path(&config, &endpoint, &path, None).await;
+                assert!(matches!(response, Err(PermissionDenied(_))));
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn route_request_authenticated() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let keys = TestKeys::generate();
+                let config = Config::new(
+                    default_test_config(),
+                    Authority::from_str(&endpoint).unwrap(),
+                    Some("cache".to_string()),
+                )
+                .with_auth(Some(keys.auth_config()));
+                let authenticator = keys.authenticator().await;
+
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    config.elsa_endpoint_authority().clone(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                let event = event_with_authorization(
+                    "data/events/event_elsa.json",
+                    &keys.bearer(TestKeys::claims("user")),
+                );
+                let caller = authenticate(Some(&authenticator), &event).unwrap();
+                assert_eq!(
+                    caller.claims().and_then(|claims| claims.subject()),
+                    Some("user")
+                );
+
+                let route = Route::try_from(&event).unwrap();
+                let response = get_resolvers(&config, &route, &caller, &endpoint).await;
+                assert!(is_manifest_resolvers(response.unwrap()));
             },
             1,
         )
         .await;
     }
 
-    async fn resolvers_from_endpoint<'a>(
+    #[tokio::test]
+    async fn route_request_unauthenticated() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let keys = TestKeys::generate();
+                let config = Config::new(
+                    default_test_config(),
+                    Authority::from_str(&endpoint).unwrap(),
+                    Some("cache".to_string()),
+                )
+                .with_auth(Some(keys.auth_config()));
+                let authenticator = keys.authenticator().await;
+
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    config.elsa_endpoint_authority().clone(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                let other_keys = TestKeys::generate();
+                for event in [
+                    from_str(
+                        &fs::read_to_string(event_path("data/events/event_elsa.json")).unwrap(),
+                    )
+                    .unwrap(),
+                    event_with_authorization(
+                        "data/events/event_elsa.json",
+                        &other_keys.bearer(TestKeys::claims("user")),
+                    ),
+                ] {
+                    let route = Route::try_from(&event).unwrap();
+                    let response =
+                        route_request(&config, event, route, Some(&authenticator), &endpoint)
+                            .await
+                            .unwrap();
+
+                    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+                }
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn route_request_granted_dataset() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let config = Config::new(
+                    default_test_config(),
+                    Authority::from_str(&endpoint).unwrap(),
+                    Some("cache".to_string()),
+                );
+
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    config.elsa_endpoint_authority().clone(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                let path = "/reads/R004/30F9F3FED8F711ED8C35DBEF59E9F537";
+
+                let caller =
+                    Caller::new(None, None).with_granted_datasets(Some(HashSet::from_iter([
+                        EXAMPLE_DATASET_URI.to_string(),
+                    ])));
+                let response = resolvers_for_caller(&config, &endpoint, path, &caller).await;
+                assert!(is_manifest_resolvers(response.unwrap()));
+
+                let caller =
+                    Caller::new(None, None).with_granted_datasets(Some(HashSet::from_iter([
+                        "urn:fdc:umccr.org:2022:dataset/other".to_string(),
+                    ])));
+                let response = resolvers_for_caller(&config, &endpoint, path, &caller).await;
+                assert!(
+                    matches!(response, Err(PermissionDenied(release_key)) if release_key == "R004")
+                );
+
+                let caller = Caller::new(None, None).with_granted_datasets(Some(HashSet::new()));
+                let response = resolvers_for_caller(&config, &endpoint, path, &caller).await;
+                assert!(matches!(response, Err(PermissionDenied(_))));
+            },
+            1,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn route_request_refused_variants() {
+        let manifest = example_elsa_manifest().replacen(
+            r#""variantSampleId": """#,
+            r#""variantSampleId": "HG00097", "sampleIds": ["HG00096", "HG00097"]"#,
+            1,
+        );
+
+        with_manifest_mocks(
+            manifest,
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let config = Config::new(
+                    default_test_config(),
+                    Authority::from_str(&endpoint).unwrap(),
+                    Some("cache".to_string()),
+                );
+
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    config.elsa_endpoint_authority().clone(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                for path in [
+                    "/variants/R004/30F9F3FED8F711ED8C35DBEF59E9F537",
+                    "/variants/R004/HG00097",
+                ] {
+                    let response = resolvers_for_path(&config, &endpoint, path, None).await;
+                    assert!(matches!(response, Err(PermissionDenied(_))));
+                }
+
+                let response = resolvers_for_path(
+                    &config,
+                    &endpoint,
+                    "/reads/R004/30F9F3FED8F711ED8C35DBEF59E9F537",
+                    None,
+                )
+                .await;
+                assert!(response.unwrap().iter().any(|resolver| resolver
+                    .regex()
+                    .is_match("R004/30F9F3FED8F711ED8C35DBEF59E9F537")));
+            },
+            1,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn route_request_refused_reads() {
+        let manifest = example_elsa_manifest().replacen(
+            r#""url": "s3://umccr-10g-data-dev/HG00097/HG00097.bam","#,
+            r#""url": "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+               "indexUrl": "s3://umccr-10g-data-dev/HG00097/HG00097.bai","#,
+            1,
+        );
+
+        with_manifest_mocks(
+            manifest,
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let config = Config::new(
+                    default_test_config(),
+                    Authority::from_str(&endpoint).unwrap(),
+                    Some("cache".to_string()),
+                );
+
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    config.elsa_endpoint_authority().clone(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                let response = resolvers_for_path(
+                    &config,
+                    &endpoint,
+                    "/reads/R004/30F9F3FED8F711ED8C35DBEF59E9F537",
+                    None,
+                )
+                .await;
+                assert!(matches!(response, Err(PermissionDenied(_))));
+
+                for path in [
+                    "/reads/R004/30F9FFD4D8F711ED8C353BBCB8861211",
+                    "/variants/R004/30F9F3FED8F711ED8C35DBEF59E9F537",
+                ] {
+                    let id = path.split_once("/R004/").unwrap().1;
+                    let response = resolvers_for_path(&config, &endpoint, path, None).await;
+                    assert!(response
+                        .unwrap()
+                        .iter()
+                        .any(|resolver| resolver.regex().is_match(&format!("R004/{id}"))));
+                }
+            },
+            1,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn route_request_ambiguous_reads() {
+        let manifest = example_elsa_manifest()
+            .replacen(
+                r#""reads": {"#,
+                r#""reads": {
+                    "30F9F3FED8F711ED8C35DBEF59E9F538": {
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.rerun.bam",
+                        "restrictions": null
+                    },"#,
+                1,
+            )
+            .replacen(
+                r#""cases": ["#,
+                r#""cases": [{
+                    "ids": {},
+                    "patients": [{
+                        "ids": {},
+                        "specimens": [{
+                            "htsgetId": "30F9F3FED8F711ED8C35DBEF59E9F538",
+                            "ids": { "": "HG00097" }
+                        }]
+                    }]
+                }, "#,
+                1,
+            );
+
+        with_manifest_mocks(
+            manifest,
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let config = Config::new(
+                    default_test_config(),
+                    Authority::from_str(&endpoint).unwrap(),
+                    Some("cache".to_string()),
+                );
+
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    config.elsa_endpoint_authority().clone(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                let response =
+                    resolvers_for_path(&config, &endpoint, "/reads/R004/HG00097", None).await;
+                assert!(matches!(response, Err(AmbiguousIdentifier(_))));
+
+                let response =
+                    resolvers_for_path(&config, &endpoint, "/variants/R004/HG00097", None).await;
+                assert!(response
+                    .unwrap()
+                    .iter()
+                    .any(|resolver| resolver.regex().is_match("R004/HG00097")));
+            },
+            1,
+        )
+        .await;
+    }
+
+    /// Read the event and set its `Authorization` header.
+    fn event_with_authorization(path: &str, authorization: &str) -> Request {
+        let mut event: Request = from_str(&fs::read_to_string(event_path(path)).unwrap()).unwrap();
+        event
+            .headers_mut()
+            .insert(AUTHORIZATION, HeaderValue::from_str(authorization).unwrap());
+
+        event
+    }
+
+    /// Get the resolvers for a request to the path, using the `event_get.json` event.
+    async fn resolvers_for_path<'a>(
+        config: &Config,
+        endpoint: &ElsaEndpoint<'a, S3, S3>,
+        path: &str,
+        authorization: Option<String>,
+    ) -> htsget_elsa::Result<Vec<Resolver>> {
+        resolvers_for_caller(config, endpoint, path, &Caller::new(authorization, None)).await
+    }
+
+    /// Get the resolvers for a request to the path by the caller, using the `event_get.json` event.
+    async fn resolvers_for_caller<'a>(
         config: &Config,
         endpoint: &ElsaEndpoint<'a, S3, S3>,
         path: &str,
-    ) -> Vec<Resolver> {
-        let path = PathBuf::from_str(env!("CARGO_MANIFEST_DIR"))
+        caller: &Caller,
+    ) -> htsget_elsa::Result<Vec<Resolver>> {
+        let event = fs::read_to_string(event_path("data/events/event_get.json"))
+            .unwrap()
+            .replace("/variants/1-vcf/sample1-bcbio-cancer", path);
+        let event = from_str(&event).unwrap();
+
+        let route = Route::try_from(&event).unwrap();
+
+        get_resolvers(config, &route, caller, endpoint).await
+    }
+
+    fn event_path(path: &str) -> PathBuf {
+        PathBuf::from_str(env!("CARGO_MANIFEST_DIR"))
             .unwrap()
             .parent()
             .unwrap()
-            .join(path);
-        let event = fs::read_to_string(path).unwrap();
+            .join(path)
+    }
+
+    async fn resolvers_from_endpoint<'a>(
+        config: &Config,
+        endpoint: &ElsaEndpoint<'a, S3, S3>,
+        path: &str,
+    ) -> htsget_elsa::Result<Vec<Resolver>> {
+        let event = fs::read_to_string(event_path(path)).unwrap();
         let event = from_str(&event).unwrap();
 
         let route = Route::try_from(&event).unwrap();
 
-        get_resolvers(config, &route, endpoint).await.unwrap()
+        get_resolvers(
+            config,
+            &route,
+            &Caller::new(authorization(&event), None),
+            endpoint,
+        )
+        .await
     }
 }
@@ -1,4 +1,3 @@
-use htsget_config::config::parser::from_path;
 use htsget_lambda::Config as HtsgetConfig;
 use lambda_http::Error;
 
@@ -8,7 +7,7 @@ use htsget_elsa_lambda::handle_request;
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     if let Some(path) = HtsgetConfig::parse_args() {
-        let config: Config = from_path(&path)?;
+        let config = Config::try_from(path.as_path())?;
 
         config.htsget_config().setup_tracing()?;
 
@@ -0,0 +1,261 @@
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use jsonwebtoken::jwk::JwkSet;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use htsget_elsa::Result;
+
+use crate::auth::{load_jwks, verify_token, Claims, JwksLocation};
+
+/// The claim of a token which contains the GA4GH passport visas.
+pub const PASSPORT_CLAIM: &str = "ga4gh_passport_v1";
+
+/// The visa type which grants access to a dataset.
+pub const CONTROLLED_ACCESS_GRANTS: &str = "ControlledAccessGrants";
+
+/// A trusted issuer of visas.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct VisaIssuerConfig {
+    issuer: String,
+    jwks: JwksLocation,
+}
+
+impl VisaIssuerConfig {
+    /// Create a new visa issuer config.
+    pub fn new(issuer: String, jwks: JwksLocation) -> Self {
+        Self { issuer, jwks }
+    }
+}
+
+/// Configuration for authorizing access to datasets using GA4GH passports.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PassportConfig {
+    visa_issuers: Vec<VisaIssuerConfig>,
+}
+
+impl PassportConfig {
+    /// Create a new passport config.
+    pub fn new(visa_issuers: Vec<VisaIssuerConfig>) -> Self {
+        Self { visa_issuers }
+    }
+
+    /// Get the trusted visa issuers.
+    pub fn visa_issuers(&self) -> &[VisaIssuerConfig] {
+        &self.visa_issuers
+    }
+}
+
+/// The claims of a visa token.
+#[derive(Deserialize, Debug)]
+struct VisaClaims {
+    ga4gh_visa_v1: Visa,
+}
+
+/// A GA4GH visa object.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Visa {
+    #[serde(rename = "type")]
+    visa_type: String,
+    asserted: u64,
+    value: String,
+    source: String,
+    by: Option<String>,
+    conditions: Option<Value>,
+}
+
+impl Visa {
+    /// Get the dataset that this visa grants access to. Only `ControlledAccessGrants` visas which
+    /// have been asserted, and which have no conditions, grant access.
+    pub fn granted_dataset(&self, now: u64) -> Option<&str> {
+        let has_conditions = self
+            .conditions
+            .as_ref()
+            .is_some_and(|conditions| !conditions.is_null());
+
+        (self.visa_type == CONTROLLED_ACCESS_GRANTS && self.asserted <= now && !has_conditions)
+            .then_some(self.value.as_str())
+    }
+}
+
+/// Validates the visas of GA4GH passports against the trusted visa issuers.
+#[derive(Debug, Clone)]
+pub struct Passports {
+    visa_issuers: Vec<(String, JwkSet)>,
+}
+
+impl Passports {
+    /// Create passports by loading the JWKS of each visa issuer in the config.
+    pub async fn from_config(config: &PassportConfig) -> Result<Self> {
+        let mut visa_issuers = Vec::with_capacity(config.visa_issuers().len());
+        for visa_issuer in config.visa_issuers() {
+            visa_issuers.push((
+                visa_issuer.issuer.to_string(),
+                load_jwks(&visa_issuer.jwks).await?,
+            ));
+        }
+
+        Ok(Self::new(visa_issuers))
+    }
+
+    /// Create passports from the issuers and their JWKS.
+    pub fn new(visa_issuers: Vec<(String, JwkSet)>) -> Self {
+        Self { visa_issuers }
+    }
+
+    /// Get the datasets granted by the valid visas in the passport claim. Visas which are not signed
+    /// by a trusted issuer, which have expired or which do not grant a dataset are ignored.
+    pub fn granted_datasets(&self, claims: &Claims) -> HashSet<String> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| now.as_secs())
+            .unwrap_or_default();
+
+        claims
+            .claim(PASSPORT_CLAIM)
+            .and_then(Value::as_array)
+            .map(|visas| {
+                visas
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .filter_map(|visa| self.verify_visa(visa))
+                    .filter_map(|visa| visa.granted_dataset(now).map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Verify a visa token against each of the trusted issuers.
+    fn verify_visa(&self, token: &str) -> Option<Visa> {
+        let visa = self.visa_issuers.iter().find_map(|(issuer, jwks)| {
+            verify_token::<VisaClaims>(token, jwks, issuer, None, &["exp", "iss"]).ok()
+        });
+
+        match visa {
+            Some(visa) => {
+                debug!(source = %visa.ga4gh_visa_v1.source, by = ?visa.ga4gh_visa_v1.by, "verified visa");
+                Some(visa.ga4gh_visa_v1)
+            }
+            None => {
+                warn!("ignoring visa which could not be verified");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::auth::tests::{now, TestKeys};
+    use crate::auth::Claims;
+    use crate::passport::{PassportConfig, Passports, VisaIssuerConfig, PASSPORT_CLAIM};
+
+    const VISA_ISSUER: &str = "https://visas.example.org";
+    const DATASET: &str = "urn:fdc:umccr.org:2022:dataset/10g";
+
+    #[tokio::test]
+    async fn granted_datasets_valid_visa() {
+        let visa_keys = TestKeys::generate();
+        let passports = passports(&visa_keys).await;
+
+        let claims = passport_claims(vec![visa_keys.sign(visa(json!({})))]);
+
+        assert_eq!(
+            passports.granted_datasets(&claims),
+            [DATASET.to_string()].into()
+        );
+    }
+
+    #[tokio::test]
+    async fn granted_datasets_untrusted_issuer() {
+        let visa_keys = TestKeys::generate();
+        let passports = passports(&visa_keys).await;
+
+        let other_keys = TestKeys::generate();
+        let claims = passport_claims(vec![other_keys.sign(visa(json!({})))]);
+
+        assert!(passports.granted_datasets(&claims).is_empty());
+    }
+
+    #[tokio::test]
+    async fn granted_datasets_expired_visa() {
+        let visa_keys = TestKeys::generate();
+        let passports = passports(&visa_keys).await;
+
+        let mut visa = visa(json!({}));
+        visa["exp"] = json!(now() - 3600);
+        let claims = passport_claims(vec![visa_keys.sign(visa)]);
+
+        assert!(passports.granted_datasets(&claims).is_empty());
+    }
+
+    #[tokio::test]
+    async fn granted_datasets_other_visa_types() {
+        let visa_keys = TestKeys::generate();
+        let passports = passports(&visa_keys).await;
+
+        let claims = passport_claims(vec![
+            visa_keys.sign(visa(json!({ "type": "AffiliationAndRole" }))),
+            visa_keys.sign(visa(json!({ "asserted": now() + 3600 }))),
+            visa_keys.sign(visa(
+                json!({ "conditions": [[{ "type": "LinkedIdentities" }]] }),
+            )),
+        ]);
+
+        assert!(passports.granted_datasets(&claims).is_empty());
+    }
+
+    #[tokio::test]
+    async fn granted_datasets_no_passport() {
+        let visa_keys = TestKeys::generate();
+        let passports = passports(&visa_keys).await;
+
+        let claims: Claims = serde_json::from_value(json!({ "iss": "issuer" })).unwrap();
+
+        assert!(passports.granted_datasets(&claims).is_empty());
+    }
+
+    async fn passports(visa_keys: &TestKeys) -> Passports {
+        Passports::from_config(&PassportConfig::new(vec![VisaIssuerConfig::new(
+            VISA_ISSUER.to_string(),
+            visa_keys.jwks_location(),
+        )]))
+        .await
+        .unwrap()
+    }
+
+    /// The claims of a ControlledAccessGrants visa for the dataset, with fields of the visa
+    /// object replaced by the overrides.
+    fn visa(overrides: Value) -> Value {
+        let mut visa = json!({
+            "type": "ControlledAccessGrants",
+            "asserted": now() - 3600,
+            "value": DATASET,
+            "source": "https://dac.example.org",
+            "by": "dac",
+        });
+        for (key, value) in overrides.as_object().unwrap() {
+            visa[key] = value.clone();
+        }
+
+        json!({
+            "iss": VISA_ISSUER,
+            "sub": "user",
+            "exp": now() + 3600,
+            "ga4gh_visa_v1": visa,
+        })
+    }
+
+    fn passport_claims(visas: Vec<String>) -> Claims {
+        serde_json::from_value(json!({
+            "iss": "https://issuer.example.org",
+            "sub": "user",
+            PASSPORT_CLAIM: visas,
+        }))
+        .unwrap()
+    }
+}
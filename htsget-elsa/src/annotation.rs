@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use htsget_config::types::Interval;
+
+use crate::Error::InvalidAnnotation;
+use crate::Result;
+
+/// The location of a gene on a contig, as a 0-based half-open interval.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneLocus {
+    chromosome: String,
+    start: u32,
+    end: u32,
+}
+
+impl GeneLocus {
+    /// Create a new gene locus from a 0-based half-open interval.
+    pub fn new(chromosome: impl Into<String>, start: u32, end: u32) -> Self {
+        Self {
+            chromosome: chromosome.into(),
+            start,
+            end,
+        }
+    }
+
+    /// Get the chromosome of the gene.
+    pub fn chromosome(&self) -> &str {
+        &self.chromosome
+    }
+
+    /// Get the interval of the gene, padded by `flank` bases on either side.
+    pub fn interval(&self, flank: u32) -> Interval {
+        Interval::new(
+            Some(self.start.saturating_sub(flank)),
+            Some(self.end.saturating_add(flank)),
+        )
+    }
+}
+
+/// The loci of genes in a reference assembly, keyed by their HGNC symbol. Symbols are matched
+/// case-insensitively, and a gene can have more than one locus, such as genes in the
+/// pseudoautosomal regions.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct GeneAnnotations {
+    genes: HashMap<String, Vec<GeneLocus>>,
+}
+
+impl fmt::Debug for GeneAnnotations {
+    /// Annotation files can contain tens of thousands of genes, so only the number of genes is
+    /// included when the options are traced.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GeneAnnotations")
+            .field("genes", &self.genes.len())
+            .finish()
+    }
+}
+
+impl GeneAnnotations {
+    /// Add a locus for a gene.
+    pub fn with_gene(mut self, symbol: &str, locus: GeneLocus) -> Self {
+        self.insert(symbol, locus);
+        self
+    }
+
+    /// Read gene annotations from a file, which is parsed as a GTF file if it ends in `.gtf`, and
+    /// as a BED file otherwise.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let reader = BufReader::new(File::open(path).map_err(|err| {
+            InvalidAnnotation(format!("failed to open `{}`: {}", path.display(), err))
+        })?);
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("gtf") => Self::from_gtf(reader),
+            _ => Self::from_bed(reader),
+        }
+    }
+
+    /// Read gene annotations from a BED file, which must have the gene symbol in its name column.
+    /// BED intervals are already 0-based half-open.
+    pub fn from_bed(reader: impl BufRead) -> Result<Self> {
+        let mut annotations = Self::default();
+        for (n, line) in reader.lines().enumerate() {
+            let line = line.map_err(|err| InvalidAnnotation(err.to_string()))?;
+            if line.trim().is_empty()
+                || line.starts_with('#')
+                || line.starts_with("track")
+                || line.starts_with("browser")
+            {
+                continue;
+            }
+
+            let columns: Vec<&str> = line.split('\t').collect();
+            let [chromosome, start, end, symbol, ..] = columns[..] else {
+                return Err(InvalidAnnotation(format!(
+                    "BED line {} must have a chromosome, start, end and name",
+                    n + 1
+                )));
+            };
+
+            annotations.insert(
+                symbol,
+                GeneLocus::new(
+                    chromosome,
+                    parse_position(start, n)?,
+                    parse_position(end, n)?,
+                ),
+            );
+        }
+
+        Ok(annotations)
+    }
+
+    /// Read gene annotations from the `gene` features of a GTF file, using their `gene_name`
+    /// attribute. GTF intervals are 1-based and inclusive, so they are converted to 0-based
+    /// half-open.
+    pub fn from_gtf(reader: impl BufRead) -> Result<Self> {
+        let mut annotations = Self::default();
+        for (n, line) in reader.lines().enumerate() {
+            let line = line.map_err(|err| InvalidAnnotation(err.to_string()))?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let columns: Vec<&str> = line.split('\t').collect();
+            let [chromosome, _, feature, start, end, _, _, _, attributes] = columns[..] else {
+                return Err(InvalidAnnotation(format!(
+                    "GTF line {} must have 9 columns",
+                    n + 1
+                )));
+            };
+            if feature != "gene" {
+                continue;
+            }
+
+            if let Some(symbol) = gtf_attribute(attributes, "gene_name") {
+                annotations.insert(
+                    symbol,
+                    GeneLocus::new(
+                        chromosome,
+                        parse_position(start, n)?.saturating_sub(1),
+                        parse_position(end, n)?,
+                    ),
+                );
+            }
+        }
+
+        Ok(annotations)
+    }
+
+    /// Get the loci of a gene, if it is annotated.
+    pub fn gene(&self, symbol: &str) -> Option<&[GeneLocus]> {
+        self.genes
+            .get(&symbol.to_ascii_uppercase())
+            .map(Vec::as_slice)
+    }
+
+    fn insert(&mut self, symbol: &str, locus: GeneLocus) {
+        self.genes
+            .entry(symbol.to_ascii_uppercase())
+            .or_default()
+            .push(locus);
+    }
+}
+
+/// Parse a position in line `n` of an annotation file.
+fn parse_position(position: &str, n: usize) -> Result<u32> {
+    position.trim().parse().map_err(|_| {
+        InvalidAnnotation(format!("invalid position `{}` on line {}", position, n + 1))
+    })
+}
+
+/// Get the value of an attribute from the attribute column of a GTF line.
+fn gtf_attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    attributes.split(';').find_map(|attribute| {
+        let (key, value) = attribute.trim().split_once(' ')?;
+        (key == name).then(|| value.trim().trim_matches('"'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use htsget_config::types::Interval;
+
+    use crate::annotation::{GeneAnnotations, GeneLocus};
+    use crate::Error::InvalidAnnotation;
+
+    #[test]
+    fn gene_annotations_from_bed() {
+        let annotations = GeneAnnotations::from_bed(
+            "track name=genes\n\
+             # comment\n\
+             17\t43044294\t43125483\tBRCA1\n\
+             X\t276321\t303356\tPLCXD1\n\
+             Y\t276321\t303356\tPLCXD1\t0\t+\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            annotations.gene("brca1").unwrap(),
+            [GeneLocus::new("17", 43044294, 43125483)]
+        );
+        assert_eq!(
+            annotations.gene("PLCXD1").unwrap(),
+            [
+                GeneLocus::new("X", 276321, 303356),
+                GeneLocus::new("Y", 276321, 303356)
+            ]
+        );
+        assert!(annotations.gene("TP53").is_none());
+    }
+
+    #[test]
+    fn gene_annotations_from_gtf() {
+        let annotations = GeneAnnotations::from_gtf(
+            "#!genome-build GRCh38\n\
+             17\tensembl\tgene\t43044295\t43125483\t.\t-\t.\tgene_id \"ENSG00000012048\"; gene_name \"BRCA1\";\n\
+             17\tensembl\ttranscript\t43044295\t43125370\t.\t-\t.\tgene_id \"ENSG00000012048\"; gene_name \"BRCA1\";\n\
+             17\tensembl\tgene\t7661779\t7687538\t.\t-\t.\tgene_id \"ENSG00000141510\";\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            annotations.gene("BRCA1").unwrap(),
+            [GeneLocus::new("17", 43044294, 43125483)]
+        );
+        assert!(annotations.gene("TP53").is_none());
+    }
+
+    #[test]
+    fn gene_annotations_invalid() {
+        assert!(matches!(
+            GeneAnnotations::from_bed("17\t43044294\t43125483\n".as_bytes()),
+            Err(InvalidAnnotation(_))
+        ));
+        assert!(matches!(
+            GeneAnnotations::from_bed("17\tstart\t43125483\tBRCA1\n".as_bytes()),
+            Err(InvalidAnnotation(_))
+        ));
+        assert!(matches!(
+            GeneAnnotations::from_gtf("17\tensembl\tgene\t1\t2\n".as_bytes()),
+            Err(InvalidAnnotation(_))
+        ));
+        assert!(matches!(
+            GeneAnnotations::from_path("does-not-exist.bed"),
+            Err(InvalidAnnotation(_))
+        ));
+    }
+
+    #[test]
+    fn gene_locus_flank() {
+        let locus = GeneLocus::new("17", 100, 200);
+
+        assert_eq!(locus.interval(0), Interval::new(Some(100), Some(200)));
+        assert_eq!(locus.interval(50), Interval::new(Some(50), Some(250)));
+        assert_eq!(locus.interval(500), Interval::new(Some(0), Some(700)));
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::{BTreeSet, HashMap};
+
+/// The number of autosomes in the built-in human contig aliases.
+const AUTOSOMES: u32 = 22;
+
+/// Aliases for contig names, which allow a restriction on a contig to apply to the same contig
+/// under a different naming convention. For example, a restriction on `1` also applies to `chr1`
+/// and `CM000663.2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContigAliases {
+    aliases: HashMap<String, BTreeSet<String>>,
+}
+
+impl Default for ContigAliases {
+    /// Create contig aliases with the built-in Ensembl, UCSC and GenBank names of the human primary
+    /// assembly contigs. `chrM` and `M` are not aliases of `MT`, because the hg19 `chrM` is a
+    /// different sequence to the GRCh37 `MT`.
+    fn default() -> Self {
+        let mut contig_aliases = Self::empty();
+
+        for n in 1..=AUTOSOMES + 2 {
+            let name = match n {
+                n if n <= AUTOSOMES => n.to_string(),
+                n if n == AUTOSOMES + 1 => "X".to_string(),
+                _ => "Y".to_string(),
+            };
+            // GenBank accessions are sequential from chromosome 1 at `CM000663`, with version 1 for
+            // GRCh37 and version 2 for GRCh38.
+            let accession = 662 + n;
+
+            contig_aliases = contig_aliases.with_aliases(
+                name.clone(),
+                [
+                    format!("chr{name}"),
+                    format!("CM{accession:06}.1"),
+                    format!("CM{accession:06}.2"),
+                ],
+            );
+        }
+
+        contig_aliases.with_aliases("MT", ["chrMT", "J01415.2"])
+    }
+}
+
+impl ContigAliases {
+    /// Create contig aliases without any of the built-in names.
+    pub fn empty() -> Self {
+        Self {
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Add aliases for a contig name. Aliases are transitive, so if any of the names already have
+    /// aliases, all of them are combined.
+    pub fn with_aliases<I, S>(mut self, name: impl Into<String>, aliases: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut group: BTreeSet<String> =
+            BTreeSet::from_iter(aliases.into_iter().map(Into::into).chain(Some(name.into())));
+
+        for name in group.clone() {
+            if let Some(existing) = self.aliases.get(&name) {
+                group.extend(existing.iter().cloned());
+            }
+        }
+
+        for name in &group {
+            self.aliases.insert(name.to_string(), group.clone());
+        }
+
+        self
+    }
+
+    /// Add aliases from a table of contig names to their aliases.
+    pub fn with_alias_table(self, table: HashMap<String, Vec<String>>) -> Self {
+        table
+            .into_iter()
+            .fold(self, |contig_aliases, (name, aliases)| {
+                contig_aliases.with_aliases(name, aliases)
+            })
+    }
+
+    /// Get all names for a contig, including the name itself.
+    pub fn aliases(&self, name: &str) -> BTreeSet<String> {
+        self.aliases
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| BTreeSet::from_iter(Some(name.to_string())))
+    }
+}
+
+/// The lengths of the GRCh37 primary assembly contigs, in the order of the built-in aliases.
+const GRCH37_LENGTHS: [u32; AUTOSOMES as usize + 3] = [
+    249250621, 243199373, 198022430, 191154276, 180915260, 171115067, 159138663, 146364022,
+    141213431, 135534747, 135006516, 133851895, 115169878, 107349540, 102531392, 90354753,
+    81195210, 78077248, 59128983, 63025520, 48129895, 51304566, 155270560, 59373566, 16569,
+];
+
+/// The length of `chrM` in hg19, which is the Yoruba mitochondrial sequence NC_001807 rather than the
+/// revised Cambridge Reference Sequence that GRCh37 uses for `MT`.
+const HG19_CHRM_LENGTH: u32 = 16571;
+
+/// The lengths of the GRCh38 primary assembly contigs, in the order of the built-in aliases.
+const GRCH38_LENGTHS: [u32; AUTOSOMES as usize + 3] = [
+    248956422, 242193529, 198295559, 190214555, 181538259, 170805979, 159345973, 145138636,
+    138394717, 133797422, 135086622, 133275309, 114364328, 107043718, 101991189, 90338345,
+    83257441, 80373285, 58617616, 64444167, 46709983, 50818468, 156040895, 57227415, 16569,
+];
+
+/// Get the canonical name of an assembly, so that UCSC names such as `hg38` match the GRC name.
+pub fn canonical_assembly(assembly: &str) -> String {
+    match assembly.to_ascii_lowercase().as_str() {
+        "grch37" | "hg19" => "GRCh37".to_string(),
+        "grch38" | "hg38" => "GRCh38".to_string(),
+        _ => assembly.to_string(),
+    }
+}
+
+/// The lengths of the contigs of a reference assembly, which are used to check that restrictions
+/// fall within their contig.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContigLengths {
+    lengths: HashMap<String, u32>,
+}
+
+impl ContigLengths {
+    /// Get the built-in contig lengths of an assembly, if there are any. Lengths are built-in for
+    /// the primary assembly contigs of GRCh37 and GRCh38, using their Ensembl names, and for the UCSC
+    /// `chrM`, which is not an alias of `MT`. The hg38 `chrM` is the same sequence as `MT`, but the
+    /// hg19 `chrM` is a different sequence.
+    pub fn built_in(assembly: &str) -> Option<Self> {
+        let (lengths, chrm_length) = match canonical_assembly(assembly).as_str() {
+            "GRCh37" => (GRCH37_LENGTHS, HG19_CHRM_LENGTH),
+            "GRCh38" => (GRCH38_LENGTHS, GRCH38_LENGTHS[AUTOSOMES as usize + 2]),
+            _ => return None,
+        };
+
+        let contig_lengths = (1..=AUTOSOMES)
+            .map(|n| n.to_string())
+            .chain(["X", "Y", "MT"].map(str::to_string))
+            .zip(lengths)
+            .fold(Self::default(), |contig_lengths, (name, length)| {
+                contig_lengths.with_length(name, length)
+            });
+
+        Some(contig_lengths.with_length("chrM", chrm_length))
+    }
+
+    /// Set the length of a contig.
+    pub fn with_length(mut self, name: impl Into<String>, length: u32) -> Self {
+        self.lengths.insert(name.into(), length);
+        self
+    }
+
+    /// Add lengths from a table of contig names to their lengths.
+    pub fn with_length_table(self, table: HashMap<String, u32>) -> Self {
+        table
+            .into_iter()
+            .fold(self, |contig_lengths, (name, length)| {
+                contig_lengths.with_length(name, length)
+            })
+    }
+
+    /// Get the names of the contigs which have a length.
+    pub fn contigs(&self) -> impl Iterator<Item = &str> {
+        self.lengths.keys().map(String::as_str)
+    }
+
+    /// Get the length of a contig, using its own name before any of its aliases, so that a contig
+    /// with a length of its own is not given the length of an alias.
+    pub fn length(&self, name: &str, contig_aliases: &ContigAliases) -> Option<u32> {
+        self.lengths.get(name).copied().or_else(|| {
+            contig_aliases
+                .aliases(name)
+                .iter()
+                .find_map(|alias| self.lengths.get(alias).copied())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use crate::contig::{canonical_assembly, ContigAliases, ContigLengths};
+
+    #[test]
+    fn default_aliases_autosome() {
+        let aliases = ContigAliases::default();
+
+        assert_eq!(
+            aliases.aliases("1"),
+            expected(&["1", "chr1", "CM000663.1", "CM000663.2"])
+        );
+        assert_eq!(aliases.aliases("chr1"), aliases.aliases("1"));
+        assert_eq!(aliases.aliases("CM000663.2"), aliases.aliases("1"));
+        assert_eq!(
+            aliases.aliases("22"),
+            expected(&["22", "chr22", "CM000684.1", "CM000684.2"])
+        );
+    }
+
+    #[test]
+    fn default_aliases_sex_chromosomes() {
+        let aliases = ContigAliases::default();
+
+        assert_eq!(
+            aliases.aliases("X"),
+            expected(&["X", "chrX", "CM000685.1", "CM000685.2"])
+        );
+        assert_eq!(
+            aliases.aliases("chrY"),
+            expected(&["Y", "chrY", "CM000686.1", "CM000686.2"])
+        );
+    }
+
+    #[test]
+    fn default_aliases_mitochondrial() {
+        let aliases = ContigAliases::default();
+
+        assert_eq!(
+            aliases.aliases("chrMT"),
+            expected(&["MT", "chrMT", "J01415.2"])
+        );
+        assert_eq!(aliases.aliases("chrM"), expected(&["chrM"]));
+        assert_eq!(aliases.aliases("M"), expected(&["M"]));
+    }
+
+    #[test]
+    fn unknown_contig() {
+        assert_eq!(
+            ContigAliases::default().aliases("chr1_KI270706v1_random"),
+            expected(&["chr1_KI270706v1_random"])
+        );
+    }
+
+    #[test]
+    fn empty_aliases() {
+        assert_eq!(ContigAliases::empty().aliases("1"), expected(&["1"]));
+    }
+
+    #[test]
+    fn alias_table() {
+        let aliases = ContigAliases::default().with_alias_table(HashMap::from_iter(vec![(
+            "chr1".to_string(),
+            vec!["Chr1".to_string()],
+        )]));
+
+        assert_eq!(
+            aliases.aliases("Chr1"),
+            expected(&["1", "chr1", "Chr1", "CM000663.1", "CM000663.2"])
+        );
+        assert_eq!(aliases.aliases("1"), aliases.aliases("Chr1"));
+    }
+
+    #[test]
+    fn aliases_are_transitive() {
+        let aliases = ContigAliases::empty()
+            .with_aliases("a", ["b"])
+            .with_aliases("c", ["d"])
+            .with_aliases("b", ["c"]);
+
+        assert_eq!(aliases.aliases("a"), expected(&["a", "b", "c", "d"]));
+        assert_eq!(aliases.aliases("d"), expected(&["a", "b", "c", "d"]));
+    }
+
+    #[test]
+    fn built_in_contig_lengths() {
+        let aliases = ContigAliases::default();
+        let grch38 = ContigLengths::built_in("hg38").unwrap();
+        let grch37 = ContigLengths::built_in("GRCh37").unwrap();
+
+        assert_eq!(grch38.length("1", &aliases), Some(248956422));
+        assert_eq!(grch38.length("chr17", &aliases), Some(83257441));
+        assert_eq!(grch38.length("chrM", &aliases), Some(16569));
+        assert_eq!(grch37.length("X", &aliases), Some(155270560));
+        assert_eq!(grch37.length("Y", &aliases), Some(59373566));
+        assert_eq!(grch37.length("MT", &aliases), Some(16569));
+        assert_eq!(grch37.length("chrM", &aliases), Some(16571));
+        assert_eq!(grch37.length("chrMT", &aliases), Some(16569));
+        assert_eq!(grch38.length("hs37d5", &aliases), None);
+        assert!(ContigLengths::built_in("CHM13").is_none());
+    }
+
+    #[test]
+    fn contig_length_table() {
+        let lengths = ContigLengths::default()
+            .with_length_table(HashMap::from_iter(vec![("chr1".to_string(), 1000)]));
+
+        assert_eq!(lengths.length("1", &ContigAliases::default()), Some(1000));
+        assert_eq!(lengths.length("1", &ContigAliases::empty()), None);
+    }
+
+    #[test]
+    fn canonical_assemblies() {
+        assert_eq!(canonical_assembly("hg19"), "GRCh37");
+        assert_eq!(canonical_assembly("grch38"), "GRCh38");
+        assert_eq!(canonical_assembly("CHM13"), "CHM13");
+    }
+
+    fn expected(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+}
@@ -1,20 +1,34 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use std::{iter, result};
 
 use async_trait::async_trait;
-use htsget_config::resolver::{AllowGuard, ReferenceNames, Resolver};
-use htsget_config::storage::s3::S3Storage;
-use htsget_config::storage::Storage;
+use chrono::DateTime;
+use htsget_config::resolver::{AllowGuard, Resolver};
 use htsget_config::types::{Format, Interval};
+use http::header::{HeaderName, AUTHORIZATION};
 use http::uri::Authority;
 use http::Uri;
-use reqwest::{Client, Url};
-use serde::Deserialize;
-use tracing::{debug, instrument};
+use reqwest::{Client, StatusCode, Url};
+use serde::{de, Deserialize, Deserializer};
+use sha2::{Digest, Sha256};
+use tracing::{debug, instrument, warn};
 
+use crate::annotation::GeneAnnotations;
+use crate::contig::{canonical_assembly, ContigAliases, ContigLengths};
+use crate::region::{allow_all_guard, Regions};
+use crate::service_auth::{ServiceAuth, FORWARDED_AUTHORIZATION};
+use crate::signature::{verify_sha256, ManifestVerifier};
+use crate::specimen::{ElsaCaseManifest, SpecimenAliases, SpecimenIds};
+use crate::storage::{check_index_url, check_reference_url, infer_format, storage_from_url};
+use crate::view::ElsaViewManifest;
 use crate::Error::{
-    DeserializeError, GetManifest, InvalidManifest, InvalidReleaseUri, UnsupportedManifestFeature,
+    DeserializeError, GetManifest, InvalidIdentifier, InvalidManifest, InvalidReleaseUri,
+    PermissionDenied, ReleaseNotActive, ReleaseNotFound, UnsupportedManifestFeature,
+};
+use crate::{
+    unix_now, Cache, Error, GetObject, ReleaseResolvers, ResolversFromElsa, Result, Revocation,
 };
-use crate::{Cache, Error, GetObject, ResolversFromElsa, Result};
 
 pub const ENDPOINT_PATH: &str = "/api/manifest/htsget";
 pub const CACHE_PATH: &str = "htsget-manifest-cache";
@@ -33,6 +47,10 @@ pub struct ElsaLocation {
 pub struct ElsaResponse {
     location: ElsaLocation,
     max_age: u64,
+    #[serde(default)]
+    sha256: Option<String>,
+    #[serde(default)]
+    signature: Option<String>,
 }
 
 /// The format of reads in the manifest.
@@ -40,8 +58,45 @@ pub struct ElsaResponse {
 #[serde(rename_all = "camelCase")]
 pub struct ElsaReadsManifest {
     url: String,
+    #[serde(default)]
+    index_url: Option<String>,
+    #[serde(default)]
+    reference_url: Option<String>,
     format: Option<Format>,
-    restrictions: Vec<ElsaRestrictionManifest>,
+    #[serde(default)]
+    assembly: Option<String>,
+    #[serde(deserialize_with = "deserialize_restrictions")]
+    restrictions: ElsaRestrictionsManifest,
+    #[serde(default)]
+    exclude: Vec<ElsaRestrictionManifest>,
+    #[serde(flatten)]
+    view: ElsaViewManifest,
+    #[serde(flatten)]
+    window: SharingWindow,
+}
+
+impl ElsaReadsManifest {
+    /// Get the url of the reference used to decode CRAM files. htsget tickets cannot carry the
+    /// reference, so files with a reference url are refused.
+    pub fn reference_url(&self) -> Option<&str> {
+        self.reference_url.as_deref()
+    }
+
+    /// Get the reason that the reads are refused rather than shared, if htsget-rs cannot serve them.
+    /// This is the case for an index at an unsupported location, or a CRAM file which needs an
+    /// external reference.
+    pub fn refusal(&self) -> Option<String> {
+        let format = infer_format(&self.url, self.format, &[Format::Bam, Format::Cram]).ok()?;
+
+        self.index_url
+            .as_deref()
+            .and_then(|index_url| refusal_reason(check_index_url(&self.url, index_url, format)))
+            .or_else(|| {
+                self.reference_url.as_deref().and_then(|reference_url| {
+                    refusal_reason(check_reference_url(reference_url, format))
+                })
+            })
+    }
 }
 
 /// The format of variants in the manifest.
@@ -49,20 +104,353 @@ pub struct ElsaReadsManifest {
 #[serde(rename_all = "camelCase")]
 pub struct ElsaVariantsManifest {
     url: String,
+    #[serde(default)]
+    index_url: Option<String>,
     format: Option<Format>,
     variant_sample_id: String,
-    restrictions: Vec<ElsaRestrictionManifest>,
+    #[serde(default)]
+    sample_ids: Option<Vec<String>>,
+    #[serde(default)]
+    assembly: Option<String>,
+    #[serde(deserialize_with = "deserialize_restrictions")]
+    restrictions: ElsaRestrictionsManifest,
+    #[serde(default)]
+    exclude: Vec<ElsaRestrictionManifest>,
+    #[serde(flatten)]
+    view: ElsaViewManifest,
+    #[serde(flatten)]
+    window: SharingWindow,
 }
 
-/// The format of the restrictions in the manifest.
-#[derive(Debug, Deserialize, PartialEq, Eq)]
+impl ElsaVariantsManifest {
+    /// Whether access to the variants can be guaranteed to only expose the `variantSampleId`.
+    /// htsget-rs cannot remove samples from a file, so this is only the case when there is no sample
+    /// restriction, or when the file is declared to contain only the restricted sample. The
+    /// `sampleIds` declared by the manifest are trusted, and are not checked against the file.
+    pub fn sample_restriction_enforceable(&self) -> bool {
+        if self.variant_sample_id.is_empty() {
+            return true;
+        }
+
+        matches!(self.sample_ids.as_deref(), Some([sample_id]) if sample_id == &self.variant_sample_id)
+    }
+
+    /// Get the reason that the variants are refused rather than shared, if htsget-rs cannot serve
+    /// them. This is the case for an index at an unsupported location.
+    pub fn refusal(&self) -> Option<String> {
+        let format = infer_format(&self.url, self.format, &[Format::Vcf, Format::Bcf]).ok()?;
+
+        self.index_url
+            .as_deref()
+            .and_then(|index_url| refusal_reason(check_index_url(&self.url, index_url, format)))
+    }
+}
+
+/// The period during which a release or a file is shared. `notBefore` is inclusive and `notAfter`
+/// is exclusive, and either can be absent to leave that side of the window open.
+#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SharingWindow {
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    not_before: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    not_after: Option<u64>,
+}
+
+impl SharingWindow {
+    /// Create a new sharing window, using seconds since the Unix epoch.
+    pub fn new(not_before: Option<u64>, not_after: Option<u64>) -> Self {
+        Self {
+            not_before,
+            not_after,
+        }
+    }
+
+    /// Whether the window is open at `now`, in seconds since the Unix epoch.
+    pub fn is_open(&self, now: u64) -> bool {
+        self.not_before.is_none_or(|not_before| not_before <= now)
+            && self.not_after.is_none_or(|not_after| now < not_after)
+    }
+
+    /// The next time after `now` at which the window opens or closes.
+    pub fn next_boundary(&self, now: u64) -> Option<u64> {
+        [self.not_before, self.not_after]
+            .into_iter()
+            .flatten()
+            .filter(|boundary| *boundary > now)
+            .min()
+    }
+}
+
+/// Deserialize a timestamp, which can be specified as either seconds since the Unix epoch or an
+/// RFC 3339 date-time, such as `1798675200` or `"2026-12-31T00:00:00Z"`.
+fn deserialize_timestamp<'de, D>(deserializer: D) -> result::Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Timestamp {
+        Seconds(u64),
+        DateTime(String),
+    }
+
+    Option::<Timestamp>::deserialize(deserializer)?
+        .map(|timestamp| match timestamp {
+            Timestamp::Seconds(seconds) => Ok(seconds),
+            Timestamp::DateTime(date_time) => DateTime::parse_from_rfc3339(&date_time)
+                .map_err(de::Error::custom)
+                .and_then(|date_time| {
+                    u64::try_from(date_time.timestamp()).map_err(de::Error::custom)
+                }),
+        })
+        .transpose()
+}
+
+/// The restrictions of a file in the manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ElsaRestrictionsManifest {
+    /// The whole file is shared, which is specified as `null` or `{ "all": true }`.
+    All,
+    /// Only the regions of the restrictions are shared. An empty list shares nothing.
+    Regions(Vec<ElsaRestrictionManifest>),
+}
+
+impl ElsaRestrictionsManifest {
+    /// Convert the positions of the restrictions into 0-based half-open positions.
+    fn convert_coordinates(&mut self, coordinates: CoordinateConvention) -> Result<()> {
+        match self {
+            ElsaRestrictionsManifest::All => Ok(()),
+            ElsaRestrictionsManifest::Regions(restrictions) => restrictions
+                .iter_mut()
+                .try_for_each(|restriction| restriction.convert_coordinates(coordinates)),
+        }
+    }
+}
+
+/// The coordinate convention of the positions of restrictions in a manifest.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CoordinateConvention {
+    /// 0-based positions with an exclusive end, as used by htsget and BED files.
+    #[default]
+    ZeroBasedHalfOpen,
+    /// 1-based positions with an inclusive end, as used by VCF files and samtools regions.
+    OneBasedClosed,
+}
+
+impl CoordinateConvention {
+    /// Convert a start position into a 0-based position. The end position is the same in both
+    /// conventions.
+    pub fn zero_based_start(self, start: Option<u32>) -> Result<Option<u32>> {
+        match (self, start) {
+            (CoordinateConvention::OneBasedClosed, Some(0)) => Err(InvalidManifest(
+                "a 1-based start position must be at least 1".to_string(),
+            )),
+            (CoordinateConvention::OneBasedClosed, Some(start)) => Ok(Some(start - 1)),
+            (_, start) => Ok(start),
+        }
+    }
+}
+
+/// Deserialize the restrictions of a file. The field must be present, so that a missing field is
+/// never treated as sharing the whole file.
+fn deserialize_restrictions<'de, D>(
+    deserializer: D,
+) -> result::Result<ElsaRestrictionsManifest, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Restrictions {
+        Regions(Vec<ElsaRestrictionManifest>),
+        All { all: bool },
+    }
+
+    match Option::<Restrictions>::deserialize(deserializer)? {
+        None | Some(Restrictions::All { all: true }) => Ok(ElsaRestrictionsManifest::All),
+        Some(Restrictions::Regions(restrictions)) => {
+            Ok(ElsaRestrictionsManifest::Regions(restrictions))
+        }
+        Some(Restrictions::All { all: false }) => Err(de::Error::custom(
+            "`all` must be true, use an empty list of restrictions to share nothing",
+        )),
+    }
+}
+
+/// The format of the restrictions in the manifest. A restriction is either an interval on a
+/// `chromosome`, or a `gene` or `panel` which is resolved into intervals using the configured gene
+/// annotations, padded by `flank` bases on either side.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct ElsaRestrictionManifest {
-    chromosome: u8,
+    #[serde(default, deserialize_with = "deserialize_chromosome")]
+    chromosome: Option<String>,
+    gene: Option<String>,
+    panel: Option<String>,
+    flank: Option<u32>,
+    start: Option<u32>,
+    end: Option<u32>,
+    #[serde(default)]
+    exclude: Vec<ElsaExclusionManifest>,
+}
+
+impl ElsaRestrictionManifest {
+    /// Resolve the restriction into the intervals that it allows on each chromosome, using the gene
+    /// annotations of the assembly for gene and panel restrictions. Chromosome restrictions are only
+    /// checked against the contig lengths of an assembly which is known.
+    fn loci(
+        &self,
+        options: &ResolverOptions,
+        assembly: Option<&str>,
+    ) -> Result<Vec<(String, Interval)>> {
+        if self.chromosome.is_none() && (self.start.is_some() || self.end.is_some()) {
+            return Err(InvalidManifest(
+                "`start` and `end` can only be set on a `chromosome` restriction".to_string(),
+            ));
+        }
+
+        match (&self.chromosome, &self.gene, &self.panel) {
+            (Some(chromosome), None, None) => {
+                if chromosome.trim().is_empty() {
+                    return Err(InvalidManifest(
+                        "restriction chromosome is empty".to_string(),
+                    ));
+                }
+                if self.flank.is_some() {
+                    return Err(InvalidManifest(
+                        "`flank` can only be set on a `gene` or `panel` restriction".to_string(),
+                    ));
+                }
+                let interval = checked_interval(self.start, self.end)?;
+                let length = assembly.and_then(|assembly| {
+                    options
+                        .contig_length(assembly, chromosome)
+                        .map(|length| (assembly, length))
+                });
+                if let Some((assembly, length)) = length {
+                    if let Some(position) = interval
+                        .start()
+                        .filter(|start| *start >= length)
+                        .or(interval.end().filter(|end| *end > length))
+                    {
+                        return Err(InvalidManifest(format!(
+                            "restriction at {position} is outside of `{chromosome}`, which has a \
+                            length of {length} in {assembly}"
+                        )));
+                    }
+                }
+
+                Ok(vec![(chromosome.to_string(), interval)])
+            }
+            (None, Some(gene), None) => self.gene_loci(gene, options, assembly),
+            (None, None, Some(panel)) => options
+                .gene_panel(panel)
+                .ok_or_else(|| InvalidManifest(format!("unknown gene panel `{panel}`")))?
+                .iter()
+                .map(|gene| self.gene_loci(gene, options, assembly))
+                .collect::<Result<Vec<_>>>()
+                .map(|loci| loci.into_iter().flatten().collect()),
+            _ => Err(InvalidManifest(
+                "a restriction must have exactly one of `chromosome`, `gene` or `panel`"
+                    .to_string(),
+            )),
+        }
+    }
+
+    /// Convert the positions of the restriction and its exclusions from the coordinate convention
+    /// of the manifest into 0-based half-open positions.
+    fn convert_coordinates(&mut self, coordinates: CoordinateConvention) -> Result<()> {
+        self.start = coordinates.zero_based_start(self.start)?;
+        for exclusion in &mut self.exclude {
+            exclusion.start = coordinates.zero_based_start(exclusion.start)?;
+        }
+
+        Ok(())
+    }
+
+    /// Resolve a gene into its intervals, padded by the flank of the restriction. Padding does not
+    /// extend past the end of the contig.
+    fn gene_loci(
+        &self,
+        gene: &str,
+        options: &ResolverOptions,
+        assembly: Option<&str>,
+    ) -> Result<Vec<(String, Interval)>> {
+        let assembly = assembly.ok_or_else(|| {
+            InvalidManifest(format!(
+                "gene `{gene}` can only be resolved for a file with an assembly"
+            ))
+        })?;
+        let flank = self.flank.unwrap_or(options.gene_flank());
+        let loci = options
+            .gene_annotations(assembly)
+            .ok_or_else(|| InvalidManifest(format!("no gene annotations for {assembly}")))?
+            .gene(gene)
+            .ok_or_else(|| InvalidManifest(format!("unknown gene `{gene}` in {assembly}")))?;
+
+        Ok(loci
+            .iter()
+            .map(|locus| {
+                let interval = locus.interval(flank);
+                let end = interval.end().map(|end| {
+                    options
+                        .contig_length(assembly, locus.chromosome())
+                        .map_or(end, |length| end.min(length))
+                });
+
+                (
+                    locus.chromosome().to_string(),
+                    Interval::new(interval.start(), end),
+                )
+            })
+            .collect())
+    }
+}
+
+/// An interval which is excluded from the allowed regions of the chromosome of a restriction.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ElsaExclusionManifest {
     start: Option<u32>,
     end: Option<u32>,
 }
 
+/// Deserialize a chromosome, which can be specified as either a number or a contig name,
+/// such as `1`, `"X"`, `"MT"` or `"chr1"`.
+fn deserialize_chromosome<'de, D>(deserializer: D) -> result::Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Chromosome {
+        Name(String),
+        Number(u64),
+    }
+
+    Ok(
+        Option::<Chromosome>::deserialize(deserializer)?.map(|chromosome| match chromosome {
+            Chromosome::Name(name) => name,
+            Chromosome::Number(number) => number.to_string(),
+        }),
+    )
+}
+
+/// The parts of a single file in the manifest which its resolvers are created from.
+#[derive(Debug, Clone, Copy)]
+struct FileParts<'a> {
+    url: &'a str,
+    id: &'a str,
+    aliases: &'a BTreeSet<String>,
+    format: Format,
+    restrictions: &'a ElsaRestrictionsManifest,
+    exclude: &'a [ElsaRestrictionManifest],
+    view: &'a ElsaViewManifest,
+    assembly: Option<&'a str>,
+}
+
 /// This defines the manifest format that htsget-elsa expects from Elsa.
 #[derive(Debug, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
@@ -71,102 +459,320 @@ pub struct ElsaManifest {
     release_key: String,
     reads: HashMap<String, ElsaReadsManifest>,
     variants: HashMap<String, ElsaVariantsManifest>,
+    #[serde(default)]
+    dataset_uris: Vec<String>,
+    #[serde(default)]
+    assembly: Option<String>,
+    #[serde(default)]
+    coordinates: CoordinateConvention,
+    #[serde(default)]
+    cases: Vec<ElsaCaseManifest>,
+    #[serde(flatten)]
+    window: SharingWindow,
+}
+
+/// Options used when converting a manifest into resolvers.
+#[derive(Debug, Clone)]
+pub struct ResolverOptions {
+    contig_aliases: ContigAliases,
+    contig_lengths: HashMap<String, ContigLengths>,
+    gene_annotations: HashMap<String, Arc<GeneAnnotations>>,
+    gene_panels: HashMap<String, Vec<String>>,
+    gene_flank: u32,
+    default_assembly: Option<String>,
+}
+
+impl Default for ResolverOptions {
+    fn default() -> Self {
+        Self {
+            contig_aliases: ContigAliases::default(),
+            contig_lengths: ["GRCh37", "GRCh38"]
+                .into_iter()
+                .filter_map(|assembly| {
+                    ContigLengths::built_in(assembly).map(|lengths| (assembly.to_string(), lengths))
+                })
+                .collect(),
+            gene_annotations: HashMap::new(),
+            gene_panels: HashMap::new(),
+            gene_flank: 0,
+            default_assembly: None,
+        }
+    }
+}
+
+impl ResolverOptions {
+    /// Set the contig aliases used to expand the reference names of restrictions.
+    pub fn with_contig_aliases(mut self, contig_aliases: ContigAliases) -> Self {
+        self.contig_aliases = contig_aliases;
+        self
+    }
+
+    /// Set the contig lengths used to check that restrictions fall within their contig for an
+    /// assembly. This replaces the built-in lengths of the assembly.
+    pub fn with_contig_lengths(mut self, assembly: &str, contig_lengths: ContigLengths) -> Self {
+        self.contig_lengths
+            .insert(canonical_assembly(assembly), contig_lengths);
+        self
+    }
+
+    /// Set the gene annotations used to resolve gene and panel restrictions for an assembly.
+    pub fn with_gene_annotations(
+        mut self,
+        assembly: &str,
+        gene_annotations: GeneAnnotations,
+    ) -> Self {
+        self.gene_annotations
+            .insert(canonical_assembly(assembly), Arc::new(gene_annotations));
+        self
+    }
+
+    /// Set the gene panels, which are named lists of gene symbols.
+    pub fn with_gene_panels(mut self, gene_panels: HashMap<String, Vec<String>>) -> Self {
+        self.gene_panels = gene_panels;
+        self
+    }
+
+    /// Set the number of bases that gene and panel restrictions are padded by on either side,
+    /// unless the restriction sets its own `flank`.
+    pub fn with_gene_flank(mut self, gene_flank: u32) -> Self {
+        self.gene_flank = gene_flank;
+        self
+    }
+
+    /// Set the assembly of files which do not have an assembly in the manifest. Without a default
+    /// assembly, restrictions of these files are not checked against contig lengths, and gene or
+    /// panel restrictions are rejected.
+    pub fn with_default_assembly(mut self, default_assembly: &str) -> Self {
+        self.default_assembly = Some(canonical_assembly(default_assembly));
+        self
+    }
+
+    /// Get the contig aliases.
+    pub fn contig_aliases(&self) -> &ContigAliases {
+        &self.contig_aliases
+    }
+
+    /// Get the contig lengths of an assembly.
+    pub fn contig_lengths(&self, assembly: &str) -> Option<&ContigLengths> {
+        self.contig_lengths.get(&canonical_assembly(assembly))
+    }
+
+    /// Get the length of a contig in an assembly, if it is known.
+    pub fn contig_length(&self, assembly: &str, contig: &str) -> Option<u32> {
+        self.contig_lengths(assembly)?
+            .length(contig, &self.contig_aliases)
+    }
+
+    /// Get the gene annotations of an assembly.
+    pub fn gene_annotations(&self, assembly: &str) -> Option<&GeneAnnotations> {
+        self.gene_annotations
+            .get(&canonical_assembly(assembly))
+            .map(Arc::as_ref)
+    }
+
+    /// Get the genes of a gene panel.
+    pub fn gene_panel(&self, panel: &str) -> Option<&[String]> {
+        self.gene_panels.get(panel).map(Vec::as_slice)
+    }
+
+    /// Get the number of bases that gene and panel restrictions are padded by.
+    pub fn gene_flank(&self) -> u32 {
+        self.gene_flank
+    }
+
+    /// Get the assembly of files which do not have an assembly in the manifest.
+    pub fn default_assembly(&self) -> Option<&str> {
+        self.default_assembly.as_deref()
+    }
 }
 
 impl ElsaManifest {
-    /// Creates a resolver from the different parts of the manifest.
-    #[instrument(level = "trace", ret)]
-    pub fn resolver_from_manifest_parts(
-        release_key: &str,
-        url: &str,
-        id: &str,
-        format: Format,
-        restriction: &ElsaRestrictionManifest,
-    ) -> Result<Resolver> {
-        let url = match (url.strip_prefix("s3://"), url.strip_prefix("S3://")) {
-            (Some(url), _) | (_, Some(url)) => Ok(url),
-            _ => Err(UnsupportedManifestFeature(
-                "only S3 manifest uris are supported".to_string(),
-            )),
-        }?;
+    /// Get the URIs of the datasets that the release belongs to.
+    pub fn dataset_uris(&self) -> &[String] {
+        &self.dataset_uris
+    }
 
-        let (bucket, key) = match url.split_once('/') {
-            Some(split) => Ok(split),
-            None => Err(InvalidManifest(
-                "could not split url into bucket and object key".to_string(),
-            )),
-        }?;
+    /// Get the sharing window of the release.
+    pub fn window(&self) -> SharingWindow {
+        self.window
+    }
 
-        if bucket.is_empty() || key.is_empty() {
-            return Err(InvalidManifest("bucket or key is empty".to_string()));
-        }
+    /// The next time after `now` at which the release, or any of its files, enters or leaves its
+    /// sharing window. Resolvers created at `now` are only valid until this time.
+    pub fn next_boundary(&self, now: u64) -> Option<u64> {
+        iter::once(&self.window)
+            .chain(self.reads.values().map(|reads| &reads.window))
+            .chain(self.variants.values().map(|variants| &variants.window))
+            .filter_map(|window| window.next_boundary(now))
+            .min()
+    }
 
-        let key = match key.to_string().strip_suffix(format.file_ending()) {
-            None => key.to_string(),
-            Some(key) => key.to_string(),
-        };
+    /// Get the specimen ids which identify more than one reads file, together with the ids of
+    /// those files. These ids are not aliases of any reads file.
+    pub fn ambiguous_reads(&self) -> BTreeMap<String, BTreeSet<String>> {
+        self.specimen_aliases().0.ambiguous().clone()
+    }
 
-        Resolver::new(
-            Storage::S3 {
-                s3_storage: S3Storage::new(bucket.to_string(), None, false),
-            },
-            &format!("^{}/{}$", release_key, id),
-            &key,
-            AllowGuard::default()
-                .with_allow_formats(vec![format])
-                .with_allow_reference_names(ReferenceNames::List(HashSet::from_iter(vec![
-                    restriction.chromosome.to_string(),
-                ])))
-                .with_allow_interval(Interval::new(restriction.start, restriction.end)),
+    /// Get the specimen ids which identify more than one variants file, together with the ids of
+    /// those files. These ids are not aliases of any variants file.
+    pub fn ambiguous_variants(&self) -> BTreeMap<String, BTreeSet<String>> {
+        self.specimen_aliases().1.ambiguous().clone()
+    }
+
+    /// Get the ids of the reads which htsget-rs cannot serve, together with the specimen ids which
+    /// identify them. These reads are refused rather than shared.
+    pub fn refused_reads(&self) -> BTreeSet<String> {
+        let (reads_aliases, _) = self.specimen_aliases();
+
+        self.reads
+            .iter()
+            .filter(|(_, reads_manifest)| reads_manifest.refusal().is_some())
+            .flat_map(|(id, _)| iter::once(id.to_string()).chain(reads_aliases.aliases(id)))
+            .collect()
+    }
+
+    /// Get the ids of the variants which cannot be restricted to their sample, or which htsget-rs
+    /// cannot serve, together with the specimen ids which identify them. These variants are refused
+    /// rather than shared.
+    pub fn refused_variants(&self) -> BTreeSet<String> {
+        let (_, variants_aliases) = self.specimen_aliases();
+
+        self.variants
+            .iter()
+            .filter(|(_, variants_manifest)| {
+                !variants_manifest.sample_restriction_enforceable()
+                    || variants_manifest.refusal().is_some()
+            })
+            .flat_map(|(id, _)| iter::once(id.to_string()).chain(variants_aliases.aliases(id)))
+            .collect()
+    }
+
+    /// Resolve the specimen ids of the cases to the reads and the variants of the release.
+    fn specimen_aliases(&self) -> (SpecimenAliases, SpecimenAliases) {
+        let specimen_ids = SpecimenIds::from_cases(&self.cases);
+
+        (
+            specimen_ids.aliases(|id| self.reads.contains_key(id)),
+            specimen_ids.aliases(|id| self.variants.contains_key(id)),
         )
-        .map_err(|err| InvalidManifest(format!("failed to construct regex: {}", err)))
     }
-}
 
-impl TryFrom<ElsaManifest> for Vec<Resolver> {
-    type Error = Error;
+    /// Convert the manifest into resolvers using the options.
+    pub fn into_resolvers(self, options: &ResolverOptions) -> Result<Vec<Resolver>> {
+        self.into_resolvers_at(options, unix_now())
+    }
 
+    /// Convert the manifest into resolvers using the options, at `now` in seconds since the Unix
+    /// epoch. Releases outside of their sharing window are refused, and files outside of their
+    /// sharing window or which htsget-rs cannot serve are left out. Files can also be queried using
+    /// the ids of their specimen, unless the specimen id is ambiguous.
     #[instrument(level = "trace", ret)]
-    fn try_from(manifest: ElsaManifest) -> Result<Self> {
-        let release_key = manifest.release_key;
-
-        let get_resolvers =
-            |url: &str, id: &str, format: Format, restrictions: Vec<ElsaRestrictionManifest>| {
-                restrictions
-                    .iter()
-                    .map(|restriction| {
-                        ElsaManifest::resolver_from_manifest_parts(
-                            &release_key,
-                            url,
-                            id,
-                            format,
-                            restriction,
-                        )
-                    })
-                    .collect()
-            };
+    pub fn into_resolvers_at(self, options: &ResolverOptions, now: u64) -> Result<Vec<Resolver>> {
+        let (reads_aliases, variants_aliases) = self.specimen_aliases();
+        let release_key = self.release_key;
+        if !self.window.is_open(now) {
+            return Err(ReleaseNotActive(release_key));
+        }
+        let release_assembly = self.assembly.as_deref().map(canonical_assembly);
+        let coordinates = self.coordinates;
 
-        Ok(manifest
+        Ok(self
             .reads
             .into_iter()
-            .map(|(id, reads_manifest)| {
-                get_resolvers(
+            .filter(|(id, reads_manifest)| is_shared(id, &reads_manifest.window, now))
+            .filter(|(id, reads_manifest)| is_servable(id, reads_manifest.refusal()))
+            .map(|(id, mut reads_manifest)| {
+                reads_manifest
+                    .restrictions
+                    .convert_coordinates(coordinates)?;
+                for exclusion in &mut reads_manifest.exclude {
+                    exclusion.convert_coordinates(coordinates)?;
+                }
+                let format = infer_format(
                     &reads_manifest.url,
+                    reads_manifest.format,
+                    &[Format::Bam, Format::Cram],
+                )?;
+                if let Some(index_url) = &reads_manifest.index_url {
+                    check_index_url(&reads_manifest.url, index_url, format)?;
+                }
+                if let Some(reference_url) = &reads_manifest.reference_url {
+                    check_reference_url(reference_url, format)?;
+                }
+                let assembly = file_assembly(
                     &id,
-                    reads_manifest.format.unwrap_or(Format::Bam),
-                    reads_manifest.restrictions,
+                    release_assembly.as_deref(),
+                    reads_manifest.assembly.as_deref(),
+                    options,
+                )?;
+
+                ElsaManifest::resolvers_from_file_parts(
+                    &release_key,
+                    &FileParts {
+                        url: &reads_manifest.url,
+                        id: &id,
+                        aliases: &reads_aliases.aliases(&id),
+                        format,
+                        restrictions: &reads_manifest.restrictions,
+                        exclude: &reads_manifest.exclude,
+                        view: &reads_manifest.view,
+                        assembly: assembly.as_deref(),
+                    },
+                    options,
                 )
             })
             .chain(
-                manifest
-                    .variants
+                self.variants
                     .into_iter()
-                    .map(|(id, variants_manifest)| {
-                        get_resolvers(
+                    .filter(|(id, variants_manifest)| is_shared(id, &variants_manifest.window, now))
+                    .filter(|(id, variants_manifest)| is_servable(id, variants_manifest.refusal()))
+                    .filter(|(id, variants_manifest)| {
+                        let enforceable = variants_manifest.sample_restriction_enforceable();
+                        if !enforceable {
+                            warn!(
+                                %id,
+                                variant_sample_id = %variants_manifest.variant_sample_id,
+                                "refusing variants that cannot be restricted to a single sample"
+                            );
+                        }
+
+                        enforceable
+                    })
+                    .map(|(id, mut variants_manifest)| {
+                        variants_manifest
+                            .restrictions
+                            .convert_coordinates(coordinates)?;
+                        for exclusion in &mut variants_manifest.exclude {
+                            exclusion.convert_coordinates(coordinates)?;
+                        }
+                        let format = infer_format(
                             &variants_manifest.url,
+                            variants_manifest.format,
+                            &[Format::Vcf, Format::Bcf],
+                        )?;
+                        if let Some(index_url) = &variants_manifest.index_url {
+                            check_index_url(&variants_manifest.url, index_url, format)?;
+                        }
+                        let assembly = file_assembly(
                             &id,
-                            variants_manifest.format.unwrap_or(Format::Vcf),
-                            variants_manifest.restrictions,
+                            release_assembly.as_deref(),
+                            variants_manifest.assembly.as_deref(),
+                            options,
+                        )?;
+
+                        ElsaManifest::resolvers_from_file_parts(
+                            &release_key,
+                            &FileParts {
+                                url: &variants_manifest.url,
+                                id: &id,
+                                aliases: &variants_aliases.aliases(&id),
+                                format,
+                                restrictions: &variants_manifest.restrictions,
+                                exclude: &variants_manifest.exclude,
+                                view: &variants_manifest.view,
+                                assembly: assembly.as_deref(),
+                            },
+                            options,
                         )
                     }),
             )
@@ -175,85 +781,497 @@ impl TryFrom<ElsaManifest> for Vec<Resolver> {
             .flatten()
             .collect())
     }
-}
-
-/// Implements fetching manifests from Elsa.
-#[derive(Debug)]
-pub struct ElsaEndpoint<'a, C, S> {
-    endpoint: Authority,
-    client: Client,
-    cache: &'a C,
-    get_object: &'a S,
-    scheme: &'a str,
-}
 
-#[async_trait]
-impl<'a, C, S> ResolversFromElsa for ElsaEndpoint<'a, C, S>
-where
-    C: Cache<Item = Vec<Resolver>, Error = Error> + Send + Sync,
-    S: GetObject<Error = Error> + Send + Sync,
-{
-    type Error = Error;
+    /// Creates the resolvers for a single file from its restrictions, which either share the whole
+    /// file or only the regions of the restrictions on the assembly of the file, less the regions of
+    /// the file's exclusions, and from the view of the file that is shared. The file can also be
+    /// queried using any of its specimen `aliases`.
+    fn resolvers_from_file_parts(
+        release_key: &str,
+        file: &FileParts,
+        options: &ResolverOptions,
+    ) -> Result<Vec<Resolver>> {
+        let FileParts {
+            url,
+            id,
+            aliases,
+            format,
+            restrictions,
+            exclude,
+            view,
+            assembly,
+        } = *file;
 
-    #[instrument(level = "debug", skip_all)]
-    async fn try_get(&self, release_key: String) -> Result<Vec<Resolver>> {
-        let cache_key = format!("{CACHE_PATH}/{release_key}");
+        let guards = match (restrictions, exclude) {
+            (ElsaRestrictionsManifest::All, []) => vec![allow_all_guard(format)],
+            (ElsaRestrictionsManifest::All, exclude) => {
+                let mut regions = Self::regions_from_assembly(id, assembly, options)?;
+                Self::remove_exclusions(&mut regions, exclude, assembly, options)?;
+                regions.allow_guards(format)
+            }
+            (ElsaRestrictionsManifest::Regions(restrictions), exclude) => {
+                if restrictions.is_empty() {
+                    debug!(%id, "file has no restrictions, so none of it is shared");
+                }
 
-        match self.cache.get(&cache_key).await {
-            Ok(Some(cached)) => Ok(cached),
-            _ => {
-                debug!("no cached response, fetching from elsa");
+                let mut regions = Self::regions_from_restrictions(restrictions, assembly, options)?;
+                Self::remove_exclusions(&mut regions, exclude, assembly, options)?;
+                regions.allow_guards(format)
+            }
+        };
+        let guards = guards
+            .into_iter()
+            .map(|guard| view.restrict_guard(guard))
+            .collect::<Result<Vec<_>>>()?;
 
-                let response = self.get_response(&release_key).await?;
-                let max_age = response.max_age;
+        Self::resolvers_from_guards(release_key, url, id, aliases, format, guards)
+    }
 
-                let resolvers: Vec<Resolver> = self.get_manifest(response).await?.try_into()?;
+    /// Creates the resolvers for a single file from the different parts of the manifest. All the
+    /// restrictions of the file are merged into one set of allowed regions, so that each region of the
+    /// file is reachable using the same id. An empty list of restrictions creates no resolvers, so
+    /// none of the file is shared.
+    #[instrument(level = "trace", ret)]
+    pub fn resolvers_from_manifest_parts(
+        release_key: &str,
+        url: &str,
+        id: &str,
+        format: Format,
+        restrictions: &[ElsaRestrictionManifest],
+        options: &ResolverOptions,
+    ) -> Result<Vec<Resolver>> {
+        let guards = Self::guards_from_restrictions(
+            id,
+            format,
+            restrictions,
+            options.default_assembly(),
+            options,
+        )?;
 
-                self.cache
-                    .put(cache_key, resolvers.clone(), max_age)
-                    .await?;
+        Self::resolvers_from_guards(release_key, url, id, &BTreeSet::new(), format, guards)
+    }
 
-                Ok(resolvers)
-            }
+    /// Creates the guards of a single file from its restrictions.
+    fn guards_from_restrictions(
+        id: &str,
+        format: Format,
+        restrictions: &[ElsaRestrictionManifest],
+        assembly: Option<&str>,
+        options: &ResolverOptions,
+    ) -> Result<Vec<AllowGuard>> {
+        if restrictions.is_empty() {
+            debug!(%id, "file has no restrictions, so none of it is shared");
         }
+
+        Ok(Self::regions_from_restrictions(restrictions, assembly, options)?.allow_guards(format))
     }
-}
 
-impl<'a, C, S> ElsaEndpoint<'a, C, S>
-where
-    C: Cache<Item = Vec<Resolver>, Error = Error>,
-    S: GetObject<Error = Error>,
-{
-    /// Create an ElsaEndpoint.
-    pub fn new(endpoint: Authority, cache: &'a C, get_object: &'a S) -> Result<Self> {
-        Ok(Self {
-            client: Self::create_client()?,
-            endpoint,
-            cache,
-            get_object,
-            scheme: "https",
-        })
+    /// Creates a resolver for each of the guards of a single file, which matches the id of the file
+    /// or any of its aliases.
+    fn resolvers_from_guards(
+        release_key: &str,
+        url: &str,
+        id: &str,
+        aliases: &BTreeSet<String>,
+        format: Format,
+        guards: Vec<AllowGuard>,
+    ) -> Result<Vec<Resolver>> {
+        validate_identifier(release_key)?;
+        let ids = iter::once(id)
+            .chain(aliases.iter().map(String::as_str))
+            .map(|id| validate_identifier(id).map(|_| regex::escape(id)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let (storage, key) = storage_from_url(url, format)?;
+        let regex = match ids.as_slice() {
+            [id] => format!("^{}/{}$", regex::escape(release_key), id),
+            ids => format!("^{}/(?:{})$", regex::escape(release_key), ids.join("|")),
+        };
+
+        guards
+            .into_iter()
+            .map(|guard| {
+                Resolver::new(storage.clone(), &regex, &key, guard)
+                    .map_err(|err| InvalidManifest(format!("failed to construct regex: {}", err)))
+            })
+            .collect()
     }
 
-    /// Create an ElsaEndpoint with a client. Used for testing where TLS is not required.
-    #[cfg(feature = "test-utils")]
-    pub fn new_with_client(
-        client: Client,
-        endpoint: Authority,
-        cache: &'a C,
-        get_object: &'a S,
-        scheme: &'a str,
-    ) -> Self {
-        Self {
-            endpoint,
-            client,
-            cache,
-            get_object,
-            scheme,
+    /// Collect the restrictions of a file into its allowed regions. Each restriction also allows the
+    /// aliases of its chromosomes. Exclusions are removed after all restrictions are added, so an
+    /// exclusion always takes precedence over any restriction which overlaps it.
+    fn regions_from_restrictions(
+        restrictions: &[ElsaRestrictionManifest],
+        assembly: Option<&str>,
+        options: &ResolverOptions,
+    ) -> Result<Regions> {
+        let loci = restrictions
+            .iter()
+            .map(|restriction| restriction.loci(options, assembly))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut regions = Regions::default();
+        for (chromosome, interval) in loci.iter().flatten() {
+            for reference_name in options.contig_aliases().aliases(chromosome) {
+                regions.insert(reference_name, *interval);
+            }
         }
-    }
 
-    fn create_client() -> Result<Client> {
+        for (restriction, loci) in restrictions.iter().zip(&loci) {
+            let chromosomes: BTreeSet<&str> = loci
+                .iter()
+                .map(|(chromosome, _)| chromosome.as_str())
+                .collect();
+            let exclusions = restriction
+                .exclude
+                .iter()
+                .map(|exclusion| checked_interval(exclusion.start, exclusion.end))
+                .collect::<Result<Vec<_>>>()?;
+            for exclusion in exclusions {
+                for reference_name in chromosomes
+                    .iter()
+                    .flat_map(|chromosome| options.contig_aliases().aliases(chromosome))
+                {
+                    regions.remove(&reference_name, exclusion);
+                }
+            }
+        }
+
+        Ok(regions)
+    }
+
+    /// Collect the whole of every contig of the assembly, and their aliases, into allowed regions.
+    /// This is used for a file which is shared with `all` but has exclusions, because a guard cannot
+    /// allow every reference name except some. Contigs without a known length are not shared.
+    fn regions_from_assembly(
+        id: &str,
+        assembly: Option<&str>,
+        options: &ResolverOptions,
+    ) -> Result<Regions> {
+        let contig_lengths = assembly
+            .and_then(|assembly| options.contig_lengths(assembly))
+            .ok_or_else(|| {
+                InvalidManifest(format!(
+                    "file `{id}` has exclusions, which need the contig lengths of its assembly when \
+                    all of the file is shared"
+                ))
+            })?;
+
+        let mut regions = Regions::default();
+        for contig in contig_lengths.contigs() {
+            for reference_name in options.contig_aliases().aliases(contig) {
+                regions.insert(reference_name, Interval::new(None, None));
+            }
+        }
+
+        Ok(regions)
+    }
+
+    /// Remove the exclusions of a file from its allowed regions. Each exclusion is a `chromosome`,
+    /// `gene` or `panel` in the same form as a restriction, and also removes the aliases of its
+    /// chromosomes.
+    fn remove_exclusions(
+        regions: &mut Regions,
+        exclude: &[ElsaRestrictionManifest],
+        assembly: Option<&str>,
+        options: &ResolverOptions,
+    ) -> Result<()> {
+        for exclusion in exclude {
+            if !exclusion.exclude.is_empty() {
+                return Err(InvalidManifest(
+                    "a file exclusion cannot have its own `exclude` list".to_string(),
+                ));
+            }
+
+            for (chromosome, interval) in exclusion.loci(options, assembly)? {
+                for reference_name in options.contig_aliases().aliases(&chromosome) {
+                    regions.remove(&reference_name, interval);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Get the assembly of a file, which is the assembly of the file, the release, or the default
+/// assembly of the options, in that order, or `None` if none of these are set. A file and release
+/// with different assemblies are rejected, because restrictions written for one assembly allow the
+/// wrong loci on another.
+fn file_assembly(
+    id: &str,
+    release_assembly: Option<&str>,
+    assembly: Option<&str>,
+    options: &ResolverOptions,
+) -> Result<Option<String>> {
+    match (release_assembly, assembly.map(canonical_assembly)) {
+        (Some(release_assembly), Some(assembly)) if release_assembly != assembly => {
+            Err(InvalidManifest(format!(
+                "file `{id}` uses {assembly}, which does not match the {release_assembly} assembly of \
+                the release"
+            )))
+        }
+        (_, Some(assembly)) => Ok(Some(assembly)),
+        (Some(release_assembly), None) => Ok(Some(release_assembly.to_string())),
+        (None, None) => Ok(options.default_assembly().map(str::to_string)),
+    }
+}
+
+/// Create a 0-based half-open interval, rejecting intervals which are empty or which start after
+/// they end.
+fn checked_interval(start: Option<u32>, end: Option<u32>) -> Result<Interval> {
+    if let (Some(start), Some(end)) = (start, end) {
+        if start >= end {
+            return Err(InvalidManifest(format!(
+                "interval from {start} to {end} is empty, as a 0-based half-open interval"
+            )));
+        }
+    }
+
+    Ok(Interval::new(start, end))
+}
+
+/// Whether a file is within its sharing window at `now`.
+fn is_shared(id: &str, window: &SharingWindow, now: u64) -> bool {
+    let shared = window.is_open(now);
+    if !shared {
+        debug!(%id, "leaving out file outside of its sharing window");
+    }
+
+    shared
+}
+
+/// Whether htsget-rs can serve a file, which is not the case if there is a reason to refuse it.
+fn is_servable(id: &str, refusal: Option<String>) -> bool {
+    match refusal {
+        Some(reason) => {
+            warn!(%id, reason, "refusing file that cannot be served");
+            false
+        }
+        None => true,
+    }
+}
+
+/// Get the reason that htsget-rs cannot serve a file from the result of checking it. Only unsupported
+/// manifest features are a reason to refuse the file. Other errors, such as invalid urls, are
+/// instead returned when converting the file into resolvers.
+fn refusal_reason(check: Result<()>) -> Option<String> {
+    match check {
+        Err(UnsupportedManifestFeature(reason)) => Some(reason),
+        _ => None,
+    }
+}
+
+/// Check that a release key or id only contains ASCII alphanumeric characters, `.`, `_` or `-`, and
+/// that it is not a relative path component.
+pub fn validate_identifier(identifier: &str) -> Result<()> {
+    let valid = !identifier.is_empty()
+        && identifier != "."
+        && identifier != ".."
+        && identifier
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+
+    if valid {
+        Ok(())
+    } else {
+        Err(InvalidIdentifier(identifier.to_string()))
+    }
+}
+
+/// Get the prefix of the cache keys of all the resolvers of a release.
+pub fn cache_prefix(release_key: &str) -> String {
+    format!("{CACHE_PATH}/{release_key}/")
+}
+
+/// Get the cache key for the resolvers of a release. Resolvers are cached separately for each
+/// authorization, using a hash so that the authorization itself is not stored in the cache.
+pub fn cache_key(release_key: &str, authorization: Option<&str>) -> String {
+    let authorization = authorization
+        .map(|authorization| hex::encode(Sha256::digest(authorization.as_bytes())))
+        .unwrap_or_else(|| "anonymous".to_string());
+
+    format!("{}{authorization}", cache_prefix(release_key))
+}
+
+impl TryFrom<ElsaManifest> for Vec<Resolver> {
+    type Error = Error;
+
+    fn try_from(manifest: ElsaManifest) -> Result<Self> {
+        manifest.into_resolvers(&ResolverOptions::default())
+    }
+}
+
+/// Implements fetching manifests from Elsa.
+#[derive(Debug)]
+pub struct ElsaEndpoint<'a, C, S> {
+    endpoint: Authority,
+    client: Client,
+    cache: &'a C,
+    get_object: &'a S,
+    scheme: &'a str,
+    resolver_options: ResolverOptions,
+    service_auth: Option<&'a ServiceAuth>,
+    manifest_verifier: Option<ManifestVerifier>,
+}
+
+#[async_trait]
+impl<'a, C, S> ResolversFromElsa for ElsaEndpoint<'a, C, S>
+where
+    C: Cache<Item = ReleaseResolvers, Error = Error> + Send + Sync,
+    S: GetObject<Error = Error> + Send + Sync,
+{
+    type Error = Error;
+
+    #[instrument(level = "debug", skip_all)]
+    async fn try_get(
+        &self,
+        release_key: String,
+        authorization: Option<String>,
+    ) -> Result<ReleaseResolvers> {
+        validate_identifier(&release_key)?;
+
+        if self.cache.revocation(&release_key).await?.is_some() {
+            debug!(%release_key, "release has been revoked");
+            return Err(PermissionDenied(release_key));
+        }
+
+        let cache_key = cache_key(&release_key, authorization.as_deref());
+        let now = unix_now();
+
+        match self.cache.get(&cache_key).await {
+            Ok(Some(cached)) if !cached.is_expired(now) => Ok(cached),
+            _ => {
+                debug!("no cached response, fetching from elsa");
+
+                let response = self
+                    .get_response(&release_key, authorization.as_deref())
+                    .await?;
+                let max_age = response.max_age;
+
+                let manifest = self.get_manifest(response).await?;
+                let dataset_uris = manifest.dataset_uris().to_vec();
+                let ambiguous_reads = manifest.ambiguous_reads();
+                let ambiguous_variants = manifest.ambiguous_variants();
+                let refused_reads = manifest.refused_reads();
+                let refused_variants = manifest.refused_variants();
+                let expires_at = manifest.next_boundary(now);
+                let max_age =
+                    expires_at.map_or(max_age, |expires_at| max_age.min(expires_at - now));
+
+                let resolvers = ReleaseResolvers::new(
+                    manifest.into_resolvers_at(&self.resolver_options, now)?,
+                    dataset_uris,
+                )
+                .with_expires_at(expires_at)
+                .with_ambiguous_reads(ambiguous_reads)
+                .with_ambiguous_variants(ambiguous_variants)
+                .with_refused_reads(refused_reads)
+                .with_refused_variants(refused_variants);
+
+                self.cache
+                    .put(cache_key, resolvers.clone(), max_age)
+                    .await?;
+
+                Ok(resolvers)
+            }
+        }
+    }
+}
+
+impl<'a, C, S> ElsaEndpoint<'a, C, S>
+where
+    C: Cache<Item = ReleaseResolvers, Error = Error>,
+    S: GetObject<Error = Error>,
+{
+    /// Create an ElsaEndpoint.
+    pub fn new(endpoint: Authority, cache: &'a C, get_object: &'a S) -> Result<Self> {
+        Ok(Self {
+            client: Self::create_client()?,
+            endpoint,
+            cache,
+            get_object,
+            scheme: "https",
+            resolver_options: ResolverOptions::default(),
+            service_auth: None,
+            manifest_verifier: None,
+        })
+    }
+
+    /// Create an ElsaEndpoint with a client. Used for testing where TLS is not required.
+    #[cfg(feature = "test-utils")]
+    pub fn new_with_client(
+        client: Client,
+        endpoint: Authority,
+        cache: &'a C,
+        get_object: &'a S,
+        scheme: &'a str,
+    ) -> Self {
+        Self {
+            endpoint,
+            client,
+            cache,
+            get_object,
+            scheme,
+            resolver_options: ResolverOptions::default(),
+            service_auth: None,
+            manifest_verifier: None,
+        }
+    }
+
+    /// Set the options used to convert manifests into resolvers.
+    pub fn with_resolver_options(mut self, resolver_options: ResolverOptions) -> Self {
+        self.resolver_options = resolver_options;
+        self
+    }
+
+    /// Set how htsget-elsa authenticates itself to Elsa. The caller's authorization is then
+    /// forwarded using the `X-Forwarded-Authorization` header.
+    pub fn with_service_auth(mut self, service_auth: Option<&'a ServiceAuth>) -> Self {
+        self.service_auth = service_auth;
+        self
+    }
+
+    /// Set the verifier used to check the signatures of manifests. If set, unsigned manifests are
+    /// rejected.
+    pub fn with_manifest_verifier(mut self, manifest_verifier: Option<ManifestVerifier>) -> Self {
+        self.manifest_verifier = manifest_verifier;
+        self
+    }
+
+    /// Remove all the cached resolvers of a release, so that the next request fetches the manifest
+    /// from Elsa. Elsa can call this when the sharing configuration of a release changes.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn purge(&self, release_key: &str) -> Result<()> {
+        validate_identifier(release_key)?;
+
+        self.cache.invalidate(cache_prefix(release_key)).await
+    }
+
+    /// Revoke a release, so that it is refused on every request regardless of what is cached, and
+    /// purge its cached resolvers.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn revoke(&self, release_key: &str) -> Result<()> {
+        validate_identifier(release_key)?;
+
+        if self.cache.revocation(release_key).await?.is_none() {
+            self.cache
+                .put_revocation(release_key, Revocation::new(unix_now()))
+                .await?;
+        }
+
+        self.purge(release_key).await
+    }
+
+    /// Reinstate a revoked release. Its resolvers are fetched from Elsa on the next request.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn reinstate(&self, release_key: &str) -> Result<()> {
+        validate_identifier(release_key)?;
+
+        self.cache.remove_revocation(release_key).await
+    }
+
+    fn create_client() -> Result<Client> {
         Client::builder()
             .use_rustls_tls()
             .https_only(true)
@@ -264,6 +1282,7 @@ where
     async fn get_response_with_scheme(
         &self,
         release_key: &str,
+        authorization: Option<&str>,
         scheme: &str,
     ) -> Result<ElsaResponse> {
         let uri = Uri::builder()
@@ -275,9 +1294,22 @@ where
             .map_err(|_| InvalidReleaseUri(release_key.to_string()))?
             .map_err(|_| InvalidReleaseUri(release_key.to_string()))?;
 
-        let response = self
-            .client
-            .get(uri)
+        let mut request = self.client.get(uri);
+        let forwarded_header = match self.service_auth {
+            Some(service_auth) => {
+                request = request.header(
+                    AUTHORIZATION,
+                    service_auth.authorization(&self.client).await?,
+                );
+                HeaderName::from_static(FORWARDED_AUTHORIZATION)
+            }
+            None => AUTHORIZATION,
+        };
+        if let Some(authorization) = authorization {
+            request = request.header(forwarded_header, authorization);
+        }
+
+        let response = request
             .send()
             .await
             .map_err(|err| GetManifest(err.to_string()))?;
@@ -287,45 +1319,93 @@ where
                 .json()
                 .await
                 .map_err(|err| DeserializeError(err.to_string()))
+        } else if response.status() == StatusCode::NOT_FOUND {
+            Err(ReleaseNotFound(release_key.to_string()))
+        } else if response.status() == StatusCode::UNAUTHORIZED
+            || response.status() == StatusCode::FORBIDDEN
+        {
+            // A cached service token may have been revoked before it expired, so it is not reused.
+            if let Some(service_auth) = self.service_auth {
+                if response.status() == StatusCode::UNAUTHORIZED {
+                    service_auth.invalidate();
+                }
+            }
+
+            Err(PermissionDenied(release_key.to_string()))
         } else {
             Err(GetManifest(response.status().to_string()))
         }
     }
 
-    /// Call the Elsa endpoint and get the response.
-    #[instrument(level = "debug", skip(self), ret)]
-    pub async fn get_response(&self, release_key: &str) -> Result<ElsaResponse> {
-        self.get_response_with_scheme(release_key, self.scheme)
+    /// Call the Elsa endpoint and get the response, forwarding the authorization if present.
+    #[instrument(level = "debug", skip(self, authorization), ret)]
+    pub async fn get_response(
+        &self,
+        release_key: &str,
+        authorization: Option<&str>,
+    ) -> Result<ElsaResponse> {
+        self.get_response_with_scheme(release_key, authorization, self.scheme)
             .await
     }
 
     /// Convert a response to a manifest by fetching it from the storage.
+    /// The manifest is checked against the hash and signature in the response before it is
+    /// deserialized.
     pub async fn get_manifest(&self, response: ElsaResponse) -> Result<ElsaManifest> {
-        self.get_object
-            .get_object(response.location.bucket, response.location.key)
-            .await
+        let manifest = self
+            .get_object
+            .get_object_bytes(response.location.bucket, response.location.key)
+            .await?;
+
+        if let Some(sha256) = &response.sha256 {
+            verify_sha256(&manifest, sha256)?;
+        }
+        if let Some(manifest_verifier) = &self.manifest_verifier {
+            manifest_verifier.verify(&manifest, response.signature.as_deref())?;
+        }
+
+        serde_json::from_slice(&manifest).map_err(|err| DeserializeError(err.to_string()))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
     use std::str::FromStr;
 
-    use htsget_config::resolver::Resolver;
-    use htsget_config::types::Format;
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use htsget_config::resolver::ReferenceNames::{List, Tagged};
+    use htsget_config::resolver::{IdResolver, Resolver};
+    use htsget_config::types::{Class, Format, Interval, Query, TaggedTypeAll};
     use http::uri::Authority;
-    use serde_json::from_str;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use serde_json::{from_str, json};
+    use sha2::{Digest, Sha256};
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
+    use crate::annotation::{GeneAnnotations, GeneLocus};
+    use crate::contig::ContigAliases;
     use crate::elsa_endpoint::{
-        ElsaEndpoint, ElsaLocation, ElsaManifest, ElsaResponse, ElsaRestrictionManifest, CACHE_PATH,
+        cache_key, validate_identifier, CoordinateConvention, ElsaEndpoint, ElsaLocation,
+        ElsaManifest, ElsaResponse, ElsaRestrictionManifest, ElsaRestrictionsManifest,
+        ResolverOptions, SharingWindow, CACHE_PATH, ENDPOINT_PATH,
     };
     use crate::s3::S3;
+    use crate::service_auth::{ClientCredentials, ServiceAuth};
+    use crate::signature::ManifestVerifier;
     use crate::test_utils::{
         example_elsa_manifest, example_elsa_response, is_manifest_resolvers,
-        is_reads_resolver_from_parts, with_test_mocks,
+        is_reads_resolver_from_parts, with_test_mocks, write_example_manifest, AUTHORIZED_RELEASE,
+        EXAMPLE_DATASET_URI, MEMBER_AUTHORIZATION,
     };
-    use crate::Error::{GetObjectError, InvalidManifest, UnsupportedManifestFeature};
-    use crate::{Cache, ResolversFromElsa};
+    use crate::Error::{
+        GetObjectError, InvalidIdentifier, InvalidManifest, InvalidManifestSignature,
+        PermissionDenied, ReleaseNotActive, ReleaseNotFound, UnsupportedManifestFeature,
+    };
+    use crate::{unix_now, Cache, ReleaseResolvers, ResolversFromElsa};
 
     #[tokio::test]
     async fn get_response() {
@@ -340,7 +1420,7 @@ mod tests {
                     "http",
                 );
 
-                let response = endpoint.get_response("R004").await.unwrap();
+                let response = endpoint.get_response("R004", None).await.unwrap();
                 assert_eq!(
                     response,
                     ElsaResponse {
@@ -349,6 +1429,8 @@ mod tests {
                             key: "htsget-manifests/R004".to_string(),
                         },
                         max_age: 86400,
+                        sha256: None,
+                        signature: None,
                     }
                 );
             },
@@ -358,7 +1440,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn get_manifest() {
+    async fn get_response_release_not_found() {
         with_test_mocks(
             |endpoint, s3_client, reqwest_client, _| async move {
                 let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
@@ -370,18 +1452,18 @@ mod tests {
                     "http",
                 );
 
-                let response = endpoint.get_response("R004").await.unwrap();
-                let manifest = endpoint.get_manifest(response).await.unwrap();
-
-                assert_eq!(manifest, from_str(&example_elsa_manifest()).unwrap());
+                let response = endpoint.get_response("R005", None).await;
+                assert!(
+                    matches!(response, Err(ReleaseNotFound(release_key)) if release_key == "R005")
+                );
             },
-            1,
+            0,
         )
         .await;
     }
 
     #[tokio::test]
-    async fn get_manifest_not_present() {
+    async fn try_get_invalid_release_key() {
         with_test_mocks(
             |endpoint, s3_client, reqwest_client, _| async move {
                 let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
@@ -393,11 +1475,8 @@ mod tests {
                     "http",
                 );
 
-                let manifest = endpoint
-                    .get_manifest(from_str(&example_elsa_response()).unwrap())
-                    .await;
-
-                assert!(matches!(manifest, Err(GetObjectError(_))));
+                let response = endpoint.try_get("R004?type=S3#".to_string(), None).await;
+                assert!(matches!(response, Err(InvalidIdentifier(_))));
             },
             0,
         )
@@ -405,7 +1484,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn try_get_cached() {
+    async fn get_response_forwards_authorization() {
         with_test_mocks(
             |endpoint, s3_client, reqwest_client, _| async move {
                 let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
@@ -417,12 +1496,10 @@ mod tests {
                     "http",
                 );
 
-                s3.put(format!("{CACHE_PATH}/R004"), vec![], 1000)
-                    .await
-                    .unwrap();
-
-                let resolvers = endpoint.try_get("R004".to_string()).await.unwrap();
-                assert!(resolvers.is_empty());
+                let response = endpoint
+                    .get_response(AUTHORIZED_RELEASE, Some(MEMBER_AUTHORIZATION))
+                    .await;
+                assert!(response.is_ok());
             },
             0,
         )
@@ -430,9 +1507,9 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn try_get_not_cached() {
+    async fn get_response_permission_denied() {
         with_test_mocks(
-            |endpoint, s3_client, reqwest_client, base_path| async move {
+            |endpoint, s3_client, reqwest_client, _| async move {
                 let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
                 let endpoint = ElsaEndpoint::new_with_client(
                     reqwest_client,
@@ -442,108 +1519,2022 @@ mod tests {
                     "http",
                 );
 
-                assert!(!base_path
-                    .join(format!("elsa-data-tmp/{CACHE_PATH}/R004"))
-                    .exists());
-                let resolvers = endpoint.try_get("R004".to_string()).await.unwrap();
+                let response = endpoint.get_response(AUTHORIZED_RELEASE, None).await;
+                assert!(matches!(response, Err(PermissionDenied(_))));
 
-                assert!(is_manifest_resolvers(resolvers));
-                assert!(base_path
-                    .join(format!("elsa-data-tmp/{CACHE_PATH}/R004"))
-                    .exists());
+                let response = endpoint
+                    .get_response(AUTHORIZED_RELEASE, Some("Bearer other"))
+                    .await;
+                assert!(matches!(response, Err(PermissionDenied(_))));
             },
-            1,
+            0,
         )
         .await;
     }
 
-    #[test]
-    fn resolvers_from_manifest() {
-        let manifest: ElsaManifest = from_str(&example_elsa_manifest()).unwrap();
-        let resolvers: Vec<Resolver> = manifest.try_into().unwrap();
-        assert!(is_manifest_resolvers(resolvers));
-    }
+    #[tokio::test]
+    async fn get_response_with_service_auth() {
+        with_test_mocks(
+            |_, s3_client, reqwest_client, _| async move {
+                let server = MockServer::start().await;
+                Mock::given(method("GET"))
+                    .and(path(format!("{ENDPOINT_PATH}/R004")))
+                    .and(header("authorization", "Bearer service"))
+                    .and(header("x-forwarded-authorization", MEMBER_AUTHORIZATION))
+                    .respond_with(
+                        ResponseTemplate::new(200).set_body_string(example_elsa_response()),
+                    )
+                    .expect(1)
+                    .mount(&server)
+                    .await;
 
-    #[test]
-    fn resolver_from_parts() {
-        let response = ElsaManifest::resolver_from_manifest_parts(
-            "R004",
-            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
-            "30F9F3FED8F711ED8C35DBEF59E9F537",
-            Format::Bam,
-            &example_restrictions_manifest(),
+                let service_auth = ServiceAuth::Bearer("service".to_string());
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    Authority::from_str(&server.address().to_string()).unwrap(),
+                    &s3,
+                    &s3,
+                    "http",
+                )
+                .with_service_auth(Some(&service_auth));
+
+                let response = endpoint
+                    .get_response("R004", Some(MEMBER_AUTHORIZATION))
+                    .await;
+                assert!(response.is_ok());
+            },
+            0,
         )
-        .unwrap();
-        assert!(is_reads_resolver_from_parts(&response));
+        .await;
     }
 
-    #[test]
-    fn resolver_from_parts_uppercase() {
-        let response = ElsaManifest::resolver_from_manifest_parts(
-            "R004",
-            "S3://umccr-10g-data-dev/HG00097/HG00097.bam",
-            "30F9F3FED8F711ED8C35DBEF59E9F537",
-            Format::Bam,
-            &example_restrictions_manifest(),
+    #[tokio::test]
+    async fn get_response_unauthorized_service_token() {
+        with_test_mocks(
+            |_, s3_client, reqwest_client, _| async move {
+                let server = MockServer::start().await;
+                Mock::given(method("POST"))
+                    .and(path("/oauth/token"))
+                    .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                        "access_token": "service",
+                        "expires_in": 3600,
+                    })))
+                    .expect(2)
+                    .mount(&server)
+                    .await;
+                Mock::given(method("GET"))
+                    .and(path(format!("{ENDPOINT_PATH}/R004")))
+                    .respond_with(ResponseTemplate::new(401))
+                    .expect(2)
+                    .mount(&server)
+                    .await;
+
+                let service_auth = ServiceAuth::ClientCredentials(ClientCredentials::new(
+                    format!("{}/oauth/token", server.uri()),
+                    "htsget-elsa".to_string(),
+                    "secret".to_string(),
+                    None,
+                ));
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    Authority::from_str(&server.address().to_string()).unwrap(),
+                    &s3,
+                    &s3,
+                    "http",
+                )
+                .with_service_auth(Some(&service_auth));
+
+                for _ in 0..2 {
+                    let response = endpoint.get_response("R004", None).await;
+                    assert!(matches!(response, Err(PermissionDenied(_))));
+                }
+            },
+            0,
         )
-        .unwrap();
-        assert!(is_reads_resolver_from_parts(&response));
+        .await;
     }
 
-    #[test]
-    fn resolver_from_parts_no_file_ending() {
-        let response = ElsaManifest::resolver_from_manifest_parts(
-            "R004",
-            "s3://umccr-10g-data-dev/HG00097/HG00097",
-            "30F9F3FED8F711ED8C35DBEF59E9F537",
-            Format::Bam,
-            &example_restrictions_manifest(),
-        )
+    #[tokio::test]
+    async fn get_manifest() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    Authority::from_str(&endpoint).unwrap(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                let response = endpoint.get_response("R004", None).await.unwrap();
+                let manifest = endpoint.get_manifest(response).await.unwrap();
+
+                assert_eq!(manifest, from_str(&example_elsa_manifest()).unwrap());
+            },
+            1,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn get_manifest_signed() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, base_path| async move {
+                let key_pair = key_pair();
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    Authority::from_str(&endpoint).unwrap(),
+                    &s3,
+                    &s3,
+                    "http",
+                )
+                .with_manifest_verifier(Some(
+                    ManifestVerifier::new(key_pair.public_key().as_ref().to_vec()).unwrap(),
+                ));
+
+                write_example_manifest(&base_path.join("elsa-data-tmp/htsget-manifests"));
+                let manifest = example_elsa_manifest();
+
+                let manifest = endpoint
+                    .get_manifest(signed_response(
+                        Some(hex::encode(Sha256::digest(manifest.as_bytes()))),
+                        Some(STANDARD.encode(key_pair.sign(manifest.as_bytes()))),
+                    ))
+                    .await
+                    .unwrap();
+                assert_eq!(manifest, from_str(&example_elsa_manifest()).unwrap());
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn get_manifest_tampered() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, base_path| async move {
+                let key_pair = key_pair();
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    Authority::from_str(&endpoint).unwrap(),
+                    &s3,
+                    &s3,
+                    "http",
+                )
+                .with_manifest_verifier(Some(
+                    ManifestVerifier::new(key_pair.public_key().as_ref().to_vec()).unwrap(),
+                ));
+
+                write_example_manifest(&base_path.join("elsa-data-tmp/htsget-manifests"));
+                let original =
+                    manifest_with_index_url("s3://umccr-10g-data-dev/HG00097/HG00097.bam.bai");
+
+                let response = endpoint
+                    .get_manifest(signed_response(
+                        Some(hex::encode(Sha256::digest(original.as_bytes()))),
+                        None,
+                    ))
+                    .await;
+                assert!(matches!(response, Err(InvalidManifestSignature(_))));
+
+                let response = endpoint
+                    .get_manifest(signed_response(
+                        None,
+                        Some(STANDARD.encode(key_pair.sign(original.as_bytes()))),
+                    ))
+                    .await;
+                assert!(matches!(response, Err(InvalidManifestSignature(_))));
+
+                let response = endpoint.get_manifest(signed_response(None, None)).await;
+                assert!(matches!(response, Err(InvalidManifestSignature(_))));
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn get_manifest_not_present() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    Authority::from_str(&endpoint).unwrap(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                let manifest = endpoint
+                    .get_manifest(from_str(&example_elsa_response()).unwrap())
+                    .await;
+
+                assert!(matches!(manifest, Err(GetObjectError(_))));
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn try_get_cached() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    Authority::from_str(&endpoint).unwrap(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                s3.put(cache_key("R004", None), ReleaseResolvers::default(), 1000)
+                    .await
+                    .unwrap();
+
+                let resolvers = endpoint.try_get("R004".to_string(), None).await.unwrap();
+                assert!(resolvers.resolvers().is_empty());
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn try_get_not_cached() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, base_path| async move {
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    Authority::from_str(&endpoint).unwrap(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                assert!(!base_path
+                    .join(format!("elsa-data-tmp/{}", cache_key("R004", None)))
+                    .exists());
+                let resolvers = endpoint.try_get("R004".to_string(), None).await.unwrap();
+
+                assert_eq!(resolvers.dataset_uris(), [EXAMPLE_DATASET_URI]);
+                assert!(is_manifest_resolvers(resolvers.into_resolvers()));
+                assert!(base_path
+                    .join(format!("elsa-data-tmp/{}", cache_key("R004", None)))
+                    .exists());
+            },
+            1,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn try_get_cached_expired() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    Authority::from_str(&endpoint).unwrap(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                s3.put(
+                    cache_key("R004", None),
+                    ReleaseResolvers::default().with_expires_at(Some(unix_now() - 1)),
+                    1000,
+                )
+                .await
+                .unwrap();
+
+                let resolvers = endpoint.try_get("R004".to_string(), None).await.unwrap();
+                assert!(is_manifest_resolvers(resolvers.into_resolvers()));
+            },
+            1,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn try_get_revoked() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, base_path| async move {
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    Authority::from_str(&endpoint).unwrap(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                endpoint.try_get("R004".to_string(), None).await.unwrap();
+                endpoint.revoke("R004").await.unwrap();
+
+                assert!(!base_path
+                    .join(format!("elsa-data-tmp/{}", cache_key("R004", None)))
+                    .exists());
+                assert!(s3.revocation("R004").await.unwrap().is_some());
+
+                let response = endpoint.try_get("R004".to_string(), None).await;
+                assert!(
+                    matches!(response, Err(PermissionDenied(release_key)) if release_key == "R004")
+                );
+
+                endpoint.reinstate("R004").await.unwrap();
+                let resolvers = endpoint.try_get("R004".to_string(), None).await.unwrap();
+                assert!(is_manifest_resolvers(resolvers.into_resolvers()));
+            },
+            2,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn purge() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, base_path| async move {
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    Authority::from_str(&endpoint).unwrap(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                for key in [
+                    cache_key("R004", None),
+                    cache_key("R004", Some(MEMBER_AUTHORIZATION)),
+                    cache_key("R0045", None),
+                ] {
+                    s3.put(key, ReleaseResolvers::default(), 1000)
+                        .await
+                        .unwrap();
+                }
+
+                endpoint.purge("R004").await.unwrap();
+
+                let cached = |key: String| base_path.join(format!("elsa-data-tmp/{key}")).exists();
+                assert!(!cached(cache_key("R004", None)));
+                assert!(!cached(cache_key("R004", Some(MEMBER_AUTHORIZATION))));
+                assert!(cached(cache_key("R0045", None)));
+                assert!(s3.revocation("R004").await.unwrap().is_none());
+
+                assert!(matches!(
+                    endpoint.purge("../R004").await,
+                    Err(InvalidIdentifier(_))
+                ));
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn try_get_cached_per_authorization() {
+        with_test_mocks(
+            |endpoint, s3_client, reqwest_client, _| async move {
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+                let endpoint = ElsaEndpoint::new_with_client(
+                    reqwest_client,
+                    Authority::from_str(&endpoint).unwrap(),
+                    &s3,
+                    &s3,
+                    "http",
+                );
+
+                let resolvers = endpoint
+                    .try_get(
+                        AUTHORIZED_RELEASE.to_string(),
+                        Some(MEMBER_AUTHORIZATION.to_string()),
+                    )
+                    .await
+                    .unwrap();
+                assert!(is_manifest_resolvers(resolvers.into_resolvers()));
+
+                assert!(s3
+                    .get(cache_key(AUTHORIZED_RELEASE, Some(MEMBER_AUTHORIZATION)))
+                    .await
+                    .unwrap()
+                    .is_some());
+                assert!(s3
+                    .get(cache_key(AUTHORIZED_RELEASE, None))
+                    .await
+                    .unwrap()
+                    .is_none());
+
+                let response = endpoint
+                    .try_get(
+                        AUTHORIZED_RELEASE.to_string(),
+                        Some("Bearer other".to_string()),
+                    )
+                    .await;
+                assert!(matches!(response, Err(PermissionDenied(_))));
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[test]
+    fn cache_key_authorization() {
+        assert_eq!(
+            cache_key("R004", None),
+            format!("{CACHE_PATH}/R004/anonymous")
+        );
+        assert_ne!(
+            cache_key("R004", Some("Bearer a")),
+            cache_key("R004", Some("Bearer b"))
+        );
+        assert!(!cache_key("R004", Some("Bearer a")).contains("Bearer"));
+    }
+
+    #[test]
+    fn resolvers_from_manifest() {
+        let manifest: ElsaManifest = from_str(&example_elsa_manifest()).unwrap();
+        let resolvers: Vec<Resolver> = manifest.try_into().unwrap();
+        assert!(is_manifest_resolvers(resolvers));
+    }
+
+    #[test]
+    fn resolver_from_parts() {
+        let response = resolvers_from_restriction(
+            "R004",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &example_restrictions_manifest(),
+        )
+        .unwrap();
+        assert_eq!(response.len(), 1);
+        assert!(is_reads_resolver_from_parts(response.first().unwrap()));
+    }
+
+    #[test]
+    fn resolver_from_parts_uppercase() {
+        let response = resolvers_from_restriction(
+            "R004",
+            "S3://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &example_restrictions_manifest(),
+        )
+        .unwrap();
+        assert_eq!(response.len(), 1);
+        assert!(is_reads_resolver_from_parts(response.first().unwrap()));
+    }
+
+    #[test]
+    fn resolver_from_parts_no_file_ending() {
+        let response = resolvers_from_restriction(
+            "R004",
+            "s3://umccr-10g-data-dev/HG00097/HG00097",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &example_restrictions_manifest(),
+        )
+        .unwrap();
+        assert_eq!(response.len(), 1);
+        assert!(is_reads_resolver_from_parts(response.first().unwrap()));
+    }
+
+    #[test]
+    fn resolver_from_parts_invalid_scheme() {
+        let response = resolvers_from_restriction(
+            "R004",
+            "gcp://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &example_restrictions_manifest(),
+        );
+        assert!(matches!(response, Err(UnsupportedManifestFeature(_))));
+    }
+
+    #[test]
+    fn resolver_from_parts_no_object_key() {
+        let response = resolvers_from_restriction(
+            "R004",
+            "s3://umccr-10g-data-dev",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &example_restrictions_manifest(),
+        );
+        assert!(matches!(response, Err(InvalidManifest(_))));
+    }
+
+    #[test]
+    fn resolver_from_parts_no_bucket() {
+        let response = resolvers_from_restriction(
+            "R004",
+            "s3:///HG00097/HG00097.bam",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &example_restrictions_manifest(),
+        );
+        assert!(matches!(response, Err(InvalidManifest(_))));
+    }
+
+    #[test]
+    fn resolver_from_parts_escapes_id() {
+        let resolvers = resolvers_from_restriction(
+            "R004",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "HG00097.bam",
+            Format::Bam,
+            &example_restrictions_manifest(),
+        )
+        .unwrap();
+        let resolver = resolvers.first().unwrap();
+
+        assert!(resolver.regex().is_match("R004/HG00097.bam"));
+        assert!(!resolver.regex().is_match("R004/HG00097xbam"));
+    }
+
+    #[test]
+    fn resolver_from_parts_escapes_release_key() {
+        let resolvers = resolvers_from_restriction(
+            "R.04",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &example_restrictions_manifest(),
+        )
+        .unwrap();
+        let resolver = resolvers.first().unwrap();
+
+        assert!(resolver
+            .regex()
+            .is_match("R.04/30F9F3FED8F711ED8C35DBEF59E9F537"));
+        assert!(!resolver
+            .regex()
+            .is_match("R004/30F9F3FED8F711ED8C35DBEF59E9F537"));
+    }
+
+    #[test]
+    fn resolver_from_parts_invalid_id() {
+        for id in ["HG00097|.*", "(HG00097)", "HG00097/HG00097", ".."] {
+            let response = resolvers_from_restriction(
+                "R004",
+                "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+                id,
+                Format::Bam,
+                &example_restrictions_manifest(),
+            );
+            assert!(matches!(response, Err(InvalidIdentifier(identifier)) if identifier == id));
+        }
+    }
+
+    #[test]
+    fn resolver_from_parts_invalid_release_key() {
+        let response = resolvers_from_restriction(
+            "R00.|.*",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &example_restrictions_manifest(),
+        );
+        assert!(matches!(response, Err(InvalidIdentifier(_))));
+    }
+
+    #[test]
+    fn validate_identifiers() {
+        assert!(validate_identifier("R004").is_ok());
+        assert!(validate_identifier("30F9F3FED8F711ED8C35DBEF59E9F537").is_ok());
+        assert!(validate_identifier("sample1-bcbio_cancer.v2").is_ok());
+        assert!(validate_identifier("").is_err());
+        assert!(validate_identifier(".").is_err());
+        assert!(validate_identifier("R004?type=S3").is_err());
+        assert!(validate_identifier("R004 ").is_err());
+    }
+
+    #[test]
+    fn restriction_numeric_chromosome() {
+        let restriction: ElsaRestrictionManifest =
+            from_str(r#"{ "chromosome": 1, "start": 1, "end": 10 }"#).unwrap();
+        assert_eq!(restriction, example_restrictions_manifest());
+    }
+
+    #[test]
+    fn restriction_named_chromosomes() {
+        for chromosome in [
+            "X",
+            "Y",
+            "MT",
+            "chrM",
+            "chr1",
+            "chr1_KI270706v1_random",
+            "hs37d5",
+        ] {
+            let restriction: ElsaRestrictionManifest =
+                from_str(&format!(r#"{{ "chromosome": "{chromosome}" }}"#)).unwrap();
+            assert_eq!(restriction.chromosome.as_deref(), Some(chromosome));
+        }
+    }
+
+    #[test]
+    fn resolver_from_parts_sex_chromosomes() {
+        for chromosome in ["X", "Y"] {
+            let response = resolvers_from_restriction(
+                "R004",
+                "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+                "30F9F3FED8F711ED8C35DBEF59E9F537",
+                Format::Bam,
+                &restrictions_manifest_for(chromosome),
+            )
+            .unwrap();
+            assert_eq!(response.len(), 1);
+            assert_eq!(
+                response.first().unwrap().allow_reference_names(),
+                &List(HashSet::from_iter(
+                    ContigAliases::default().aliases(chromosome)
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn resolver_from_parts_mitochondrial() {
+        for chromosome in ["MT", "chrM"] {
+            let response = resolvers_from_restriction(
+                "R004",
+                "s3://umccr-10g-data-dev/HG00097/HG00097.hard-filtered.vcf.gz",
+                "30F9F3FED8F711ED8C35DBEF59E9F537",
+                Format::Vcf,
+                &restrictions_manifest_for(chromosome),
+            )
+            .unwrap();
+            assert_eq!(response.len(), 1);
+            assert_eq!(
+                response.first().unwrap().allow_reference_names(),
+                &List(HashSet::from_iter(
+                    ContigAliases::default().aliases(chromosome)
+                ))
+            );
+            assert!(matches!(
+                response.first().unwrap().allow_reference_names(),
+                List(names) if !names.contains(if chromosome == "MT" { "chrM" } else { "MT" })
+            ));
+        }
+    }
+
+    #[test]
+    fn resolver_from_parts_empty_chromosome() {
+        let response = resolvers_from_restriction(
+            "R004",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &restrictions_manifest_for(""),
+        );
+        assert!(matches!(response, Err(InvalidManifest(_))));
+    }
+
+    #[test]
+    fn resolvers_from_parts_multiple_restrictions() {
+        let restrictions = gene_panel_restrictions();
+        let resolvers = ElsaManifest::resolvers_from_manifest_parts(
+            "R004",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &restrictions,
+            &ResolverOptions::default(),
+        )
+        .unwrap();
+
+        assert!(resolvers
+            .iter()
+            .all(|resolver| resolver.regex().to_string()
+                == "^R004/30F9F3FED8F711ED8C35DBEF59E9F537$"));
+
+        // htsget-rs treats the end of an allowed interval as exclusive, so a query must end before it.
+        for restriction in &restrictions {
+            let reference_name = restriction.chromosome.as_deref();
+            assert!(resolves_region(
+                &resolvers,
+                reference_name,
+                Interval::new(restriction.start, restriction.end.map(|end| end - 1))
+            ));
+            assert!(!resolves_region(
+                &resolvers,
+                reference_name,
+                Interval::new(restriction.start, restriction.end)
+            ));
+        }
+    }
+
+    #[test]
+    fn resolvers_from_parts_outside_restrictions() {
+        let resolvers = ElsaManifest::resolvers_from_manifest_parts(
+            "R004",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &gene_panel_restrictions(),
+            &ResolverOptions::default(),
+        )
+        .unwrap();
+
+        for (reference_name, interval) in [
+            ("1", Interval::new(Some(500), Some(1500))),
+            ("1", Interval::new(Some(1500), Some(2500))),
+            ("X", Interval::new(Some(1000), Some(1100))),
+            ("2", Interval::new(None, None)),
+        ] {
+            assert!(!resolves_region(&resolvers, Some(reference_name), interval));
+        }
+    }
+
+    #[test]
+    fn resolvers_from_parts_exclusions() {
+        let restrictions: Vec<ElsaRestrictionManifest> = from_str(
+            r#"[{ "chromosome": 19, "exclude": [{ "start": 44905000, "end": 44910000 }] }]"#,
+        )
+        .unwrap();
+        let resolvers = ElsaManifest::resolvers_from_manifest_parts(
+            "R004",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &restrictions,
+            &ResolverOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(resolvers.len(), 2);
+        for (reference_name, interval, allowed) in [
+            ("19", Interval::new(Some(0), Some(44904999)), true),
+            ("chr19", Interval::new(Some(44910000), Some(58617616)), true),
+            ("19", Interval::new(Some(0), Some(44905000)), false),
+            ("19", Interval::new(Some(44906000), Some(44907000)), false),
+            (
+                "chr19",
+                Interval::new(Some(44900000), Some(44906000)),
+                false,
+            ),
+            ("19", Interval::new(None, None), false),
+        ] {
+            assert_eq!(
+                resolves_region(&resolvers, Some(reference_name), interval),
+                allowed
+            );
+        }
+    }
+
+    #[test]
+    fn resolvers_from_parts_exclusions_take_precedence() {
+        let restrictions: Vec<ElsaRestrictionManifest> = from_str(
+            r#"[
+                { "chromosome": 1, "start": 100, "end": 200, "exclude": [{ "start": 150, "end": 300 }] },
+                { "chromosome": 1, "start": 250, "end": 400 },
+                { "chromosome": 2, "exclude": [{}] }
+            ]"#,
+        )
+        .unwrap();
+        let resolvers = ElsaManifest::resolvers_from_manifest_parts(
+            "R004",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &restrictions,
+            &ResolverOptions::default().with_contig_aliases(ContigAliases::empty()),
+        )
+        .unwrap();
+
+        let mut intervals: Vec<Interval> = resolvers
+            .iter()
+            .map(|resolver| resolver.allow_interval())
+            .collect();
+        intervals.sort_by_key(|interval| interval.start());
+        assert_eq!(
+            intervals,
+            vec![
+                Interval::new(Some(100), Some(150)),
+                Interval::new(Some(300), Some(400))
+            ]
+        );
+        assert!(resolvers
+            .iter()
+            .all(|resolver| resolver.allow_reference_names()
+                == &List(HashSet::from_iter(vec!["1".to_string()]))));
+    }
+
+    #[test]
+    fn resolvers_from_parts_genes() {
+        let restrictions: Vec<ElsaRestrictionManifest> = from_str(
+            r#"[
+                { "gene": "brca1" },
+                { "gene": "PLCXD1", "flank": 1000, "exclude": [{ "start": 290000 }] }
+            ]"#,
+        )
+        .unwrap();
+        let resolvers = ElsaManifest::resolvers_from_manifest_parts(
+            "R004",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &restrictions,
+            &gene_options(),
+        )
+        .unwrap();
+
+        let regions = [
+            ("17", 43044194, 43125582),
+            ("chr17", 43044194, 43125582),
+            ("X", 275321, 289999),
+            ("Y", 275321, 289999),
+        ];
+        for (reference_name, start, end) in regions {
+            assert!(resolves_region(
+                &resolvers,
+                Some(reference_name),
+                Interval::new(Some(start), Some(end))
+            ));
+        }
+        assert!(!resolves_region(
+            &resolvers,
+            Some("X"),
+            Interval::new(Some(290000), Some(304356))
+        ));
+    }
+
+    #[test]
+    fn resolvers_from_parts_panel() {
+        let restrictions: Vec<ElsaRestrictionManifest> =
+            from_str(r#"[{ "panel": "hereditary-cancer", "flank": 0 }]"#).unwrap();
+        let resolvers = ElsaManifest::resolvers_from_manifest_parts(
+            "R004",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &restrictions,
+            &gene_options().with_contig_aliases(ContigAliases::empty()),
+        )
+        .unwrap();
+
+        let mut intervals: Vec<Interval> = resolvers
+            .iter()
+            .map(|resolver| resolver.allow_interval())
+            .collect();
+        intervals.sort_by_key(|interval| interval.start());
+        assert_eq!(
+            intervals,
+            vec![
+                Interval::new(Some(7661778), Some(7687538)),
+                Interval::new(Some(43044294), Some(43125483))
+            ]
+        );
+    }
+
+    #[test]
+    fn resolvers_from_parts_invalid_genes() {
+        for restriction in [
+            r#"{ "gene": "NOTAGENE" }"#,
+            r#"{ "panel": "unknown" }"#,
+            r#"{ "gene": "BRCA1", "panel": "hereditary-cancer" }"#,
+            r#"{ "chromosome": 17, "gene": "BRCA1" }"#,
+            r#"{ "gene": "BRCA1", "start": 1 }"#,
+            r#"{ "chromosome": 17, "flank": 100 }"#,
+            r#"{}"#,
+        ] {
+            let restriction: ElsaRestrictionManifest = from_str(restriction).unwrap();
+            assert!(matches!(
+                ElsaManifest::resolvers_from_manifest_parts(
+                    "R004",
+                    "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+                    "30F9F3FED8F711ED8C35DBEF59E9F537",
+                    Format::Bam,
+                    &[restriction],
+                    &gene_options(),
+                ),
+                Err(InvalidManifest(_))
+            ));
+        }
+
+        let restriction: ElsaRestrictionManifest = from_str(r#"{ "gene": "BRCA1" }"#).unwrap();
+        assert!(matches!(
+            ElsaManifest::resolvers_from_manifest_parts(
+                "R004",
+                "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+                "30F9F3FED8F711ED8C35DBEF59E9F537",
+                Format::Bam,
+                &[restriction],
+                &gene_options().with_default_assembly("GRCh37"),
+            ),
+            Err(InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn resolvers_from_parts_merge_shared_intervals() {
+        let resolvers = ElsaManifest::resolvers_from_manifest_parts(
+            "R004",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &[
+                restrictions_manifest_for("X"),
+                restrictions_manifest_for("Y"),
+                restrictions_manifest_for("MT"),
+            ],
+            &ResolverOptions::default().with_contig_aliases(ContigAliases::empty()),
+        )
+        .unwrap();
+
+        assert_eq!(resolvers.len(), 1);
+        assert_eq!(
+            resolvers.first().unwrap().allow_reference_names(),
+            &List(HashSet::from_iter(vec![
+                "X".to_string(),
+                "Y".to_string(),
+                "MT".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn resolvers_from_parts_contig_aliases() {
+        let resolvers = ElsaManifest::resolvers_from_manifest_parts(
+            "R004",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &[example_restrictions_manifest()],
+            &ResolverOptions::default(),
+        )
+        .unwrap();
+
+        let interval = Interval::new(Some(1), Some(9));
+        for reference_name in ["1", "chr1", "CM000663.2"] {
+            assert!(resolves_region(&resolvers, Some(reference_name), interval));
+        }
+        assert!(!resolves_region(&resolvers, Some("chr2"), interval));
+    }
+
+    #[test]
+    fn resolvers_from_parts_alias_table() {
+        let resolvers = ElsaManifest::resolvers_from_manifest_parts(
+            "R004",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "30F9F3FED8F711ED8C35DBEF59E9F537",
+            Format::Bam,
+            &[example_restrictions_manifest()],
+            &ResolverOptions::default().with_contig_aliases(
+                ContigAliases::default().with_alias_table(HashMap::from_iter(vec![(
+                    "1".to_string(),
+                    vec!["NC_000001.11".to_string()],
+                )])),
+            ),
+        )
+        .unwrap();
+
+        assert!(resolves_region(
+            &resolvers,
+            Some("NC_000001.11"),
+            Interval::new(Some(1), Some(9))
+        ));
+    }
+
+    #[test]
+    fn sharing_window() {
+        let window = SharingWindow::new(Some(100), Some(200));
+
+        assert!(!window.is_open(99));
+        assert!(window.is_open(100));
+        assert!(window.is_open(199));
+        assert!(!window.is_open(200));
+
+        assert_eq!(window.next_boundary(50), Some(100));
+        assert_eq!(window.next_boundary(100), Some(200));
+        assert_eq!(window.next_boundary(200), None);
+        assert!(SharingWindow::default().is_open(0));
+        assert_eq!(SharingWindow::default().next_boundary(0), None);
+    }
+
+    #[test]
+    fn sharing_window_timestamps() {
+        let window: SharingWindow =
+            from_str(r#"{ "notBefore": 1767225600, "notAfter": "2026-12-31T00:00:00+10:00" }"#)
+                .unwrap();
+        assert_eq!(
+            window,
+            SharingWindow::new(Some(1767225600), Some(1798639200))
+        );
+
+        assert!(from_str::<SharingWindow>(r#"{ "notAfter": "2026-12-31" }"#).is_err());
+    }
+
+    #[test]
+    fn resolvers_from_manifest_release_not_active() {
+        let now = unix_now();
+
+        for window in [
+            format!(r#""notAfter": {}"#, now - 1),
+            format!(r#""notBefore": {}"#, now + 3600),
+        ] {
+            let manifest: ElsaManifest = from_str(&manifest_with_windows(&window, "")).unwrap();
+
+            assert!(matches!(
+                manifest.into_resolvers(&ResolverOptions::default()),
+                Err(ReleaseNotActive(release_key)) if release_key == "R004"
+            ));
+        }
+    }
+
+    #[test]
+    fn resolvers_from_manifest_file_windows() {
+        let now = unix_now();
+        let manifest: ElsaManifest = from_str(&manifest_with_windows(
+            &format!(r#""notAfter": {}"#, now + 7200),
+            &format!(r#""notBefore": {}"#, now + 3600),
+        ))
+        .unwrap();
+
+        assert_eq!(manifest.next_boundary(now), Some(now + 3600));
+        assert_eq!(
+            manifest
+                .into_resolvers_at(&ResolverOptions::default(), now)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let manifest: ElsaManifest = from_str(&manifest_with_windows(
+            &format!(r#""notAfter": {}"#, now + 7200),
+            &format!(r#""notBefore": {}"#, now + 3600),
+        ))
+        .unwrap();
+
+        assert_eq!(manifest.next_boundary(now + 3600), Some(now + 7200));
+        assert_eq!(
+            manifest
+                .into_resolvers_at(&ResolverOptions::default(), now + 3600)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn resolvers_from_manifest_ambiguous_specimen_id() {
+        let manifest: ElsaManifest = from_str(&example_elsa_manifest().replacen(
+            r#""": "HG00096""#,
+            r#""": "HG00096", "family": "HG00097""#,
+            1,
+        ))
+        .unwrap();
+
+        let ambiguous = BTreeMap::from_iter([(
+            "HG00097".to_string(),
+            BTreeSet::from_iter([
+                "30F9F3FED8F711ED8C35DBEF59E9F537".to_string(),
+                "30F9FFD4D8F711ED8C353BBCB8861211".to_string(),
+            ]),
+        )]);
+        assert_eq!(manifest.ambiguous_reads(), ambiguous);
+        assert_eq!(manifest.ambiguous_variants(), ambiguous);
+
+        let resolvers: Vec<Resolver> = manifest.try_into().unwrap();
+        assert!(resolvers
+            .iter()
+            .all(|resolver| !resolver.regex().is_match("R004/HG00097")));
+        assert!(resolvers
+            .iter()
+            .any(|resolver| resolver.regex().is_match("R004/HG00096")));
+    }
+
+    #[test]
+    fn resolvers_from_manifest_multi_sample_variants() {
+        let manifest: ElsaManifest = from_str(&manifest_with_variant_sample(
+            "\"variantSampleId\": \"HG00097\"",
+        ))
+        .unwrap();
+        assert_eq!(
+            manifest.refused_variants(),
+            BTreeSet::from_iter(["30F9F3FED8F711ED8C35DBEF59E9F537".to_string()])
+        );
+
+        let resolvers: Vec<Resolver> = manifest.try_into().unwrap();
+        assert!(resolvers.is_empty());
+    }
+
+    #[test]
+    fn resolvers_from_manifest_undeclared_samples() {
+        let manifest: ElsaManifest = from_str(&manifest_with_variant_sample(
+            r#""variantSampleId": "HG00097", "sampleIds": ["HG00096", "HG00097"]"#,
+        ))
+        .unwrap();
+        let resolvers: Vec<Resolver> = manifest.try_into().unwrap();
+
+        assert!(resolvers.is_empty());
+    }
+
+    #[test]
+    fn resolvers_from_manifest_single_sample_variants() {
+        let manifest: ElsaManifest = from_str(&manifest_with_variant_sample(
+            r#""variantSampleId": "HG00097", "sampleIds": ["HG00097"]"#,
+        ))
+        .unwrap();
+        assert!(manifest.refused_variants().is_empty());
+
+        let resolvers: Vec<Resolver> = manifest.try_into().unwrap();
+
+        assert_eq!(resolvers.len(), 1);
+        assert_eq!(resolvers.first().unwrap().allow_formats(), [Format::Vcf]);
+    }
+
+    #[test]
+    fn restrictions_all() {
+        for restrictions in ["null", r#"{ "all": true }"#] {
+            let manifest: ElsaManifest =
+                from_str(&manifest_with_restrictions(restrictions)).unwrap();
+            assert_eq!(
+                manifest.reads.values().next().unwrap().restrictions,
+                ElsaRestrictionsManifest::All
+            );
+
+            let resolvers: Vec<Resolver> = manifest.try_into().unwrap();
+            assert_eq!(resolvers.len(), 1);
+
+            let resolver = resolvers.first().unwrap();
+            assert_eq!(
+                resolver.allow_reference_names(),
+                &Tagged(TaggedTypeAll::All)
+            );
+            assert_eq!(resolver.allow_interval(), Interval::new(None, None));
+            assert!(resolves_region(
+                &resolvers,
+                Some("chr7"),
+                Interval::new(Some(100), Some(200))
+            ));
+        }
+    }
+
+    #[test]
+    fn restrictions_all_with_exclusions() {
+        let manifest: ElsaManifest = from_str(&manifest_with_exclusions(
+            r#"{ "all": true }"#,
+            r#"[{ "chromosome": 19, "start": 44905000, "end": 44910000 }, { "gene": "BRCA1" }]"#,
+        ))
         .unwrap();
-        assert!(is_reads_resolver_from_parts(&response));
+        let resolvers = manifest.into_resolvers(&gene_options()).unwrap();
+
+        assert!(resolvers
+            .iter()
+            .all(|resolver| matches!(resolver.allow_reference_names(), List(_))));
+        for (reference_name, interval, allowed) in [
+            (Some("chr7"), Interval::new(Some(100), Some(200)), true),
+            (Some("1"), Interval::new(None, None), true),
+            (Some("19"), Interval::new(Some(0), Some(44904999)), true),
+            (Some("19"), Interval::new(Some(0), Some(44905000)), false),
+            (Some("chr19"), Interval::new(Some(44910000), None), true),
+            (
+                Some("19"),
+                Interval::new(Some(44906000), Some(44907000)),
+                false,
+            ),
+            (Some("19"), Interval::new(None, None), false),
+            (
+                Some("17"),
+                Interval::new(Some(43044300), Some(43044400)),
+                false,
+            ),
+            (
+                Some("chr17"),
+                Interval::new(Some(7661778), Some(7687538)),
+                true,
+            ),
+            (Some("GL000192.1"), Interval::new(None, None), false),
+            (None, Interval::new(None, None), false),
+        ] {
+            assert_eq!(
+                resolves_region(&resolvers, reference_name, interval),
+                allowed
+            );
+        }
     }
 
     #[test]
-    fn resolver_from_parts_invalid_scheme() {
-        let response = ElsaManifest::resolver_from_manifest_parts(
+    fn restrictions_with_file_exclusions() {
+        let manifest: ElsaManifest = from_str(&manifest_with_exclusions(
+            r#"[{ "chromosome": 17 }, { "chromosome": 19, "start": 100, "end": 200 }]"#,
+            r#"[{ "panel": "hereditary-cancer", "flank": 0 }, { "chromosome": 19 }]"#,
+        ))
+        .unwrap();
+        let resolvers = manifest.into_resolvers(&gene_options()).unwrap();
+
+        for (reference_name, interval, allowed) in [
+            ("17", Interval::new(Some(0), Some(7661777)), true),
+            ("chr17", Interval::new(Some(7687538), Some(43044293)), true),
+            ("17", Interval::new(Some(0), Some(7661778)), false),
+            ("17", Interval::new(Some(7661778), Some(7661779)), false),
+            ("17", Interval::new(Some(43125482), Some(43125483)), false),
+            ("19", Interval::new(Some(100), Some(200)), false),
+        ] {
+            assert_eq!(
+                resolves_region(&resolvers, Some(reference_name), interval),
+                allowed
+            );
+        }
+    }
+
+    #[test]
+    fn restrictions_all_with_exclusions_unknown_assembly() {
+        let manifest: ElsaManifest = from_str(
+            &manifest_with_exclusions(r#"{ "all": true }"#, r#"[{ "chromosome": 1 }]"#).replacen(
+                r#""variants": {}"#,
+                r#""variants": {}, "assembly": "CHM13""#,
+                1,
+            ),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            manifest.into_resolvers(&ResolverOptions::default()),
+            Err(InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn file_exclusions_cannot_be_nested() {
+        let manifest: ElsaManifest = from_str(&manifest_with_exclusions(
+            "null",
+            r#"[{ "chromosome": 1, "exclude": [{ "start": 100 }] }]"#,
+        ))
+        .unwrap();
+
+        assert!(matches!(
+            manifest.into_resolvers(&ResolverOptions::default()),
+            Err(InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn resolvers_from_manifest_view() {
+        let manifest: ElsaManifest = from_str(
+            r#"{
+                "id": "R004",
+                "reads": {
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+                        "restrictions": [{ "chromosome": 1 }, { "chromosome": 2, "start": 100 }],
+                        "classes": ["header"]
+                    }
+                },
+                "variants": {}
+            }"#,
+        )
+        .unwrap();
+        let resolvers: Vec<Resolver> = manifest.try_into().unwrap();
+
+        assert_eq!(resolvers.len(), 2);
+        assert!(resolvers
+            .iter()
+            .all(|resolver| resolver.allow_classes() == [Class::Header]));
+    }
+
+    #[test]
+    fn resolvers_from_manifest_invalid_view() {
+        let manifest: ElsaManifest = from_str(
+            r#"{
+                "id": "R004",
+                "reads": {},
+                "variants": {
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.hard-filtered.vcf.gz",
+                        "variantSampleId": "",
+                        "restrictions": null,
+                        "fields": ["CHROM", "POS"]
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            Vec::<Resolver>::try_from(manifest),
+            Err(UnsupportedManifestFeature(_))
+        ));
+    }
+
+    #[test]
+    fn restrictions_empty_shares_nothing() {
+        let manifest: ElsaManifest = from_str(&manifest_with_restrictions("[]")).unwrap();
+        assert_eq!(
+            manifest.reads.values().next().unwrap().restrictions,
+            ElsaRestrictionsManifest::Regions(vec![])
+        );
+
+        let resolvers: Vec<Resolver> = manifest.try_into().unwrap();
+        assert!(resolvers.is_empty());
+    }
+
+    #[test]
+    fn resolvers_from_manifest_assemblies() {
+        for (release_assembly, file_assembly) in
+            [("", ""), ("GRCh38", ""), ("", "hg38"), ("hg38", "GRCh38")]
+        {
+            let manifest: ElsaManifest = from_str(&manifest_with_assemblies(
+                release_assembly,
+                file_assembly,
+                r#"[{ "chromosome": 17, "start": 83000000, "end": 83257441 }]"#,
+            ))
+            .unwrap();
+
+            assert_eq!(Vec::<Resolver>::try_from(manifest).unwrap().len(), 1);
+        }
+    }
+
+    #[test]
+    fn resolvers_from_manifest_assembly_mismatch() {
+        let manifest: ElsaManifest = from_str(&manifest_with_assemblies(
+            "GRCh37",
+            "GRCh38",
+            r#"[{ "chromosome": 1 }]"#,
+        ))
+        .unwrap();
+
+        assert!(matches!(
+            Vec::<Resolver>::try_from(manifest),
+            Err(InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn resolvers_from_manifest_outside_contig() {
+        for (assembly, restriction) in [
+            ("GRCh38", r#"{ "chromosome": 17, "end": 83257442 }"#),
+            ("hg38", r#"{ "chromosome": "chr17", "start": 83257441 }"#),
+            ("GRCh37", r#"{ "chromosome": 17, "end": 83257441 }"#),
+            (
+                "hg19",
+                r#"{ "chromosome": "MT", "start": 100, "end": 20000 }"#,
+            ),
+            ("hg19", r#"{ "chromosome": "chrM", "end": 16572 }"#),
+            ("hg38", r#"{ "chromosome": "chrM", "end": 16570 }"#),
+        ] {
+            let manifest: ElsaManifest = from_str(&manifest_with_assemblies(
+                assembly,
+                "",
+                &format!("[{restriction}]"),
+            ))
+            .unwrap();
+
+            assert!(matches!(
+                Vec::<Resolver>::try_from(manifest),
+                Err(InvalidManifest(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn resolvers_from_manifest_within_contig() {
+        for (assembly, restriction) in [
+            ("", r#"{ "chromosome": 17, "end": 400000000 }"#),
+            ("hg19", r#"{ "chromosome": "chrM", "end": 16571 }"#),
+            ("GRCh37", r#"{ "chromosome": "MT", "end": 16569 }"#),
+        ] {
+            let manifest: ElsaManifest = from_str(&manifest_with_assemblies(
+                assembly,
+                "",
+                &format!("[{restriction}]"),
+            ))
+            .unwrap();
+
+            assert_eq!(Vec::<Resolver>::try_from(manifest).unwrap().len(), 1);
+        }
+    }
+
+    #[test]
+    fn resolvers_from_manifest_gene_without_assembly() {
+        let manifest: ElsaManifest =
+            from_str(&manifest_with_restrictions(r#"[{ "gene": "BRCA1" }]"#)).unwrap();
+        let options = gene_options();
+
+        assert!(matches!(
+            manifest.into_resolvers(&ResolverOptions {
+                default_assembly: None,
+                ..options
+            }),
+            Err(InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn resolvers_from_parts_unknown_contig_length() {
+        let restriction: ElsaRestrictionManifest =
+            from_str(r#"{ "chromosome": "hs37d5", "end": 400000000 }"#).unwrap();
+
+        assert!(resolvers_from_restriction(
             "R004",
-            "gcp://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
             "30F9F3FED8F711ED8C35DBEF59E9F537",
             Format::Bam,
-            &example_restrictions_manifest(),
-        );
-        assert!(matches!(response, Err(UnsupportedManifestFeature(_))));
+            &restriction,
+        )
+        .is_ok());
     }
 
     #[test]
-    fn resolver_from_parts_no_object_key() {
-        let response = ElsaManifest::resolver_from_manifest_parts(
+    fn resolvers_from_parts_gene_flank_within_contig() {
+        let restriction: ElsaRestrictionManifest =
+            from_str(r#"{ "gene": "MT-CYB", "flank": 1000 }"#).unwrap();
+        let resolvers = ElsaManifest::resolvers_from_manifest_parts(
             "R004",
-            "s3://umccr-10g-data-dev",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
             "30F9F3FED8F711ED8C35DBEF59E9F537",
             Format::Bam,
-            &example_restrictions_manifest(),
+            &[restriction],
+            &gene_options().with_contig_aliases(ContigAliases::empty()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            resolvers.first().unwrap().allow_interval(),
+            Interval::new(Some(13746), Some(16569))
         );
-        assert!(matches!(response, Err(InvalidManifest(_))));
     }
 
     #[test]
-    fn resolver_from_parts_no_bucket() {
-        let response = ElsaManifest::resolver_from_manifest_parts(
+    fn resolvers_from_manifest_one_based_coordinates() {
+        let manifest: ElsaManifest = from_str(&one_based_manifest(
+            r#"[{ "chromosome": 1, "start": 1, "end": 10, "exclude": [{ "start": 5, "end": 5 }] }]"#,
+        ))
+        .unwrap();
+        let resolvers: Vec<Resolver> = manifest.try_into().unwrap();
+
+        let intervals: HashSet<(Option<u32>, Option<u32>)> = resolvers
+            .iter()
+            .map(|resolver| {
+                let interval = resolver.allow_interval();
+                (interval.start(), interval.end())
+            })
+            .collect();
+        assert_eq!(
+            intervals,
+            HashSet::from_iter([(Some(0), Some(4)), (Some(5), Some(10))])
+        );
+    }
+
+    #[test]
+    fn resolvers_from_parts_merge_adjacent_restrictions() {
+        let restrictions: Vec<ElsaRestrictionManifest> = from_str(
+            r#"[
+                { "chromosome": 1, "start": 100, "end": 200 },
+                { "chromosome": 1, "start": 200, "end": 300 },
+                { "chromosome": 1, "start": 250, "end": 400 }
+            ]"#,
+        )
+        .unwrap();
+        let resolvers = ElsaManifest::resolvers_from_manifest_parts(
             "R004",
-            "s3:///HG00097/HG00097.bam",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
             "30F9F3FED8F711ED8C35DBEF59E9F537",
             Format::Bam,
-            &example_restrictions_manifest(),
+            &restrictions,
+            &ResolverOptions::default().with_contig_aliases(ContigAliases::empty()),
+        )
+        .unwrap();
+
+        assert_eq!(resolvers.len(), 1);
+        assert_eq!(
+            resolvers.first().unwrap().allow_interval(),
+            Interval::new(Some(100), Some(400))
         );
-        assert!(matches!(response, Err(InvalidManifest(_))));
+    }
+
+    #[test]
+    fn resolvers_from_manifest_invalid_intervals() {
+        for restriction in [
+            r#"{ "chromosome": 1, "start": 10, "end": 10 }"#,
+            r#"{ "chromosome": 1, "start": 20, "end": 10 }"#,
+            r#"{ "chromosome": 1, "exclude": [{ "start": 20, "end": 10 }] }"#,
+        ] {
+            let manifest: ElsaManifest =
+                from_str(&manifest_with_restrictions(&format!("[{restriction}]"))).unwrap();
+            assert!(matches!(
+                Vec::<Resolver>::try_from(manifest),
+                Err(InvalidManifest(_))
+            ));
+        }
+
+        for restriction in [
+            r#"{ "chromosome": 1, "start": 0, "end": 10 }"#,
+            r#"{ "chromosome": 1, "start": 11, "end": 10 }"#,
+            r#"{ "chromosome": 1, "exclude": [{ "start": 0 }] }"#,
+        ] {
+            let manifest: ElsaManifest =
+                from_str(&one_based_manifest(&format!("[{restriction}]"))).unwrap();
+            assert!(matches!(
+                Vec::<Resolver>::try_from(manifest),
+                Err(InvalidManifest(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn coordinate_conventions() {
+        assert_eq!(
+            CoordinateConvention::ZeroBasedHalfOpen
+                .zero_based_start(Some(0))
+                .unwrap(),
+            Some(0)
+        );
+        assert_eq!(
+            CoordinateConvention::OneBasedClosed
+                .zero_based_start(Some(1))
+                .unwrap(),
+            Some(0)
+        );
+        assert_eq!(
+            CoordinateConvention::OneBasedClosed
+                .zero_based_start(None)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            from_str::<CoordinateConvention>(r#""oneBasedClosed""#).unwrap(),
+            CoordinateConvention::OneBasedClosed
+        );
+    }
+
+    #[test]
+    fn restrictions_invalid() {
+        assert!(
+            from_str::<ElsaManifest>(&manifest_with_restrictions(r#"{ "all": false }"#)).is_err()
+        );
+        assert!(
+            from_str::<ElsaManifest>(&manifest_with_restrictions(r#"{ "chromosome": 1 }"#))
+                .is_err()
+        );
+        assert!(from_str::<ElsaManifest>(
+            r#"{
+                "id": "R004",
+                "reads": {
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.bam"
+                    }
+                },
+                "variants": {}
+            }"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn resolvers_from_manifest_no_sample_restriction() {
+        let manifest: ElsaManifest =
+            from_str(&manifest_with_variant_sample("\"variantSampleId\": \"\"")).unwrap();
+        let resolvers: Vec<Resolver> = manifest.try_into().unwrap();
+
+        assert_eq!(resolvers.len(), 1);
+    }
+
+    #[test]
+    fn resolvers_from_manifest_index_url() {
+        let manifest: ElsaManifest = from_str(&manifest_with_index_url(
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam.bai",
+        ))
+        .unwrap();
+        let resolvers: Vec<Resolver> = manifest.try_into().unwrap();
+
+        assert!(is_reads_resolver_from_parts(resolvers.first().unwrap()));
+    }
+
+    #[test]
+    fn resolvers_from_manifest_index_url_different_location() {
+        let manifest: ElsaManifest = from_str(&manifest_with_index_url(
+            "s3://umccr-10g-data-dev/indexes/HG00097.bam.bai",
+        ))
+        .unwrap();
+        assert_eq!(
+            manifest.refused_reads(),
+            BTreeSet::from_iter(vec!["30F9F3FED8F711ED8C35DBEF59E9F537".to_string()])
+        );
+
+        let resolvers: Vec<Resolver> = manifest.try_into().unwrap();
+        assert!(resolvers.is_empty());
+    }
+
+    #[test]
+    fn resolvers_from_manifest_refuses_only_file_with_index_url() {
+        let manifest: ElsaManifest = from_str(
+            r#"{
+                "id": "R004",
+                "reads": {
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+                        "restrictions": [{ "chromosome": 1, "start": 1, "end": 10 }]
+                    },
+                    "30F9FFD4D8F711ED8C353BBCB8861211": {
+                        "url": "s3://umccr-10g-data-dev/HG00096/HG00096.bam",
+                        "indexUrl": "s3://umccr-10g-data-dev/HG00096/HG00096.bai",
+                        "restrictions": null
+                    }
+                },
+                "variants": {
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.hard-filtered.vcf.gz",
+                        "indexUrl": "s3://umccr-10g-data-dev/indexes/HG00097.hard-filtered.vcf.gz.tbi",
+                        "variantSampleId": "",
+                        "restrictions": null
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            manifest.refused_reads(),
+            BTreeSet::from_iter(vec!["30F9FFD4D8F711ED8C353BBCB8861211".to_string()])
+        );
+        assert_eq!(
+            manifest.refused_variants(),
+            BTreeSet::from_iter(vec!["30F9F3FED8F711ED8C35DBEF59E9F537".to_string()])
+        );
+
+        let resolvers: Vec<Resolver> = manifest.try_into().unwrap();
+        assert_eq!(resolvers.len(), 1);
+        assert!(is_reads_resolver_from_parts(resolvers.first().unwrap()));
+    }
+
+    #[test]
+    fn resolvers_from_manifest_cram_and_bcf() {
+        let manifest: ElsaManifest = from_str(
+            r#"{
+                "id": "R004",
+                "reads": {
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.cram",
+                        "restrictions": [{ "chromosome": 1 }]
+                    }
+                },
+                "variants": {
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.bcf",
+                        "variantSampleId": "",
+                        "restrictions": [{ "chromosome": 1 }]
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let resolvers: Vec<Resolver> = manifest.try_into().unwrap();
+
+        assert!(resolvers.iter().any(|resolver| {
+            resolver.allow_formats() == [Format::Cram]
+                && resolver.substitution_string() == "HG00097/HG00097"
+        }));
+        assert!(resolvers.iter().any(|resolver| {
+            resolver.allow_formats() == [Format::Bcf]
+                && resolver.substitution_string() == "HG00097/HG00097"
+        }));
+    }
+
+    #[test]
+    fn resolvers_from_manifest_contradicting_format() {
+        let manifest: ElsaManifest = from_str(
+            r#"{
+                "id": "R004",
+                "reads": {
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.cram",
+                        "format": "BAM",
+                        "restrictions": [{ "chromosome": 1 }]
+                    }
+                },
+                "variants": {}
+            }"#,
+        )
+        .unwrap();
+        let resolvers: Result<Vec<Resolver>, _> = manifest.try_into();
+
+        assert!(matches!(resolvers, Err(InvalidManifest(_))));
+    }
+
+    #[test]
+    fn resolvers_from_manifest_cram_with_reference() {
+        let manifest: ElsaManifest = from_str(
+            r#"{
+                "id": "R004",
+                "reads": {
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.cram",
+                        "referenceUrl": "s3://umccr-10g-data-dev/reference/GRCh38.fa",
+                        "restrictions": [{ "chromosome": 1 }]
+                    },
+                    "30F9FFD4D8F711ED8C353BBCB8861211": {
+                        "url": "s3://umccr-10g-data-dev/HG00096/HG00096.cram",
+                        "restrictions": [{ "chromosome": 1 }]
+                    }
+                },
+                "variants": {
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.bcf",
+                        "variantSampleId": "",
+                        "restrictions": [{ "chromosome": 1 }]
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            manifest.refused_reads(),
+            BTreeSet::from_iter(vec!["30F9F3FED8F711ED8C35DBEF59E9F537".to_string()])
+        );
+        assert!(manifest.refused_variants().is_empty());
+
+        let resolvers: Vec<Resolver> = manifest.try_into().unwrap();
+        assert_eq!(resolvers.len(), 2);
+        assert!(resolvers.iter().any(|resolver| {
+            resolver.allow_formats() == [Format::Cram]
+                && resolver.substitution_string() == "HG00096/HG00096"
+        }));
+        assert!(resolvers.iter().any(|resolver| {
+            resolver.allow_formats() == [Format::Bcf]
+                && resolver.substitution_string() == "HG00097/HG00097"
+        }));
+        assert!(!resolvers
+            .iter()
+            .any(|resolver| resolver.allow_formats() == [Format::Cram]
+                && resolver.substitution_string() == "HG00097/HG00097"));
+    }
+
+    #[test]
+    fn resolvers_from_manifest_reference_url_not_cram() {
+        let manifest: ElsaManifest = from_str(
+            r#"{
+                "id": "R004",
+                "reads": {
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+                        "referenceUrl": "s3://umccr-10g-data-dev/reference/GRCh38.fa",
+                        "restrictions": [{ "chromosome": 1 }]
+                    }
+                },
+                "variants": {}
+            }"#,
+        )
+        .unwrap();
+        let resolvers: Result<Vec<Resolver>, _> = manifest.try_into();
+
+        assert!(matches!(resolvers, Err(InvalidManifest(_))));
+    }
+
+    /// A response for the example manifest with a hash and signature.
+    fn signed_response(sha256: Option<String>, signature: Option<String>) -> ElsaResponse {
+        ElsaResponse {
+            location: ElsaLocation {
+                bucket: "elsa-data-tmp".to_string(),
+                key: "htsget-manifests/R004".to_string(),
+            },
+            max_age: 86400,
+            sha256,
+            signature,
+        }
+    }
+
+    fn key_pair() -> Ed25519KeyPair {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+    }
+
+    fn manifest_with_index_url(index_url: &str) -> String {
+        format!(
+            r#"{{
+                "id": "R004",
+                "reads": {{
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {{
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+                        "indexUrl": "{index_url}",
+                        "restrictions": [{{ "chromosome": 1, "start": 1, "end": 10 }}]
+                    }}
+                }},
+                "variants": {{}}
+            }}"#
+        )
+    }
+
+    /// A manifest with two reads files, where the release and the second file have the windows.
+    fn manifest_with_windows(release_window: &str, file_window: &str) -> String {
+        let release_window = if release_window.is_empty() {
+            String::new()
+        } else {
+            format!("{release_window},")
+        };
+        let file_window = if file_window.is_empty() {
+            String::new()
+        } else {
+            format!(",{file_window}")
+        };
+
+        format!(
+            r#"{{
+                "id": "R004",
+                {release_window}
+                "reads": {{
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {{
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+                        "restrictions": [{{ "chromosome": 1 }}]
+                    }},
+                    "30F9FFD4D8F711ED8C353BBCB8861211": {{
+                        "url": "s3://umccr-10g-data-dev/HG00096/HG00096.bam",
+                        "restrictions": [{{ "chromosome": 1 }}]
+                        {file_window}
+                    }}
+                }},
+                "variants": {{}}
+            }}"#
+        )
+    }
+
+    fn manifest_with_assemblies(
+        release_assembly: &str,
+        file_assembly: &str,
+        restrictions: &str,
+    ) -> String {
+        let release_assembly = if release_assembly.is_empty() {
+            String::new()
+        } else {
+            format!(r#""assembly": "{release_assembly}","#)
+        };
+        let file_assembly = if file_assembly.is_empty() {
+            String::new()
+        } else {
+            format!(r#""assembly": "{file_assembly}","#)
+        };
+
+        format!(
+            r#"{{
+                "id": "R004",
+                {release_assembly}
+                "reads": {{
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {{
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+                        {file_assembly}
+                        "restrictions": {restrictions}
+                    }}
+                }},
+                "variants": {{}}
+            }}"#
+        )
+    }
+
+    fn one_based_manifest(restrictions: &str) -> String {
+        manifest_with_restrictions(restrictions).replacen(
+            r#""id": "R004","#,
+            r#""id": "R004", "coordinates": "oneBasedClosed","#,
+            1,
+        )
+    }
+
+    fn manifest_with_restrictions(restrictions: &str) -> String {
+        format!(
+            r#"{{
+                "id": "R004",
+                "reads": {{
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {{
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+                        "restrictions": {restrictions}
+                    }}
+                }},
+                "variants": {{}}
+            }}"#
+        )
+    }
+
+    fn manifest_with_exclusions(restrictions: &str, exclude: &str) -> String {
+        format!(
+            r#"{{
+                "id": "R004",
+                "reads": {{
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {{
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+                        "restrictions": {restrictions},
+                        "exclude": {exclude}
+                    }}
+                }},
+                "variants": {{}}
+            }}"#
+        )
+    }
+
+    fn manifest_with_variant_sample(sample: &str) -> String {
+        format!(
+            r#"{{
+                "id": "R004",
+                "reads": {{}},
+                "variants": {{
+                    "30F9F3FED8F711ED8C35DBEF59E9F537": {{
+                        "url": "s3://umccr-10g-data-dev/HG00097/HG00097.hard-filtered.vcf.gz",
+                        "restrictions": [{{ "chromosome": 1 }}],
+                        {sample}
+                    }}
+                }}
+            }}"#
+        )
+    }
+
+    /// A panel of 50 regions spread over several chromosomes.
+    /// Creates the resolvers for a file with a single restriction using the default options.
+    fn resolvers_from_restriction(
+        release_key: &str,
+        url: &str,
+        id: &str,
+        format: Format,
+        restriction: &ElsaRestrictionManifest,
+    ) -> crate::Result<Vec<Resolver>> {
+        ElsaManifest::resolvers_from_manifest_parts(
+            release_key,
+            url,
+            id,
+            format,
+            std::slice::from_ref(restriction),
+            &ResolverOptions::default(),
+        )
+    }
+
+    /// Check whether htsget-rs resolves a query for a region of the
+    /// `30F9F3FED8F711ED8C35DBEF59E9F537` reads using the resolvers.
+    fn resolves_region(
+        resolvers: &[Resolver],
+        reference_name: Option<&str>,
+        interval: Interval,
+    ) -> bool {
+        let mut query =
+            Query::new_with_default_request("R004/30F9F3FED8F711ED8C35DBEF59E9F537", Format::Bam);
+        if let Some(reference_name) = reference_name {
+            query = query.with_reference_name(reference_name);
+        }
+        if let Some(start) = interval.start() {
+            query = query.with_start(start);
+        }
+        if let Some(end) = interval.end() {
+            query = query.with_end(end);
+        }
+
+        resolvers.resolve_id(&query).is_some()
+    }
+
+    fn gene_panel_restrictions() -> Vec<ElsaRestrictionManifest> {
+        (0..50)
+            .map(|n| ElsaRestrictionManifest {
+                start: Some((n as u32 + 1) * 1000),
+                end: Some((n as u32 + 1) * 1000 + 400),
+                ..restrictions_manifest_for(["1", "7", "17", "X"][n % 4])
+            })
+            .collect()
+    }
+
+    /// Resolver options with gene annotations for GRCh38, padding genes by 100 bases.
+    fn gene_options() -> ResolverOptions {
+        ResolverOptions::default()
+            .with_gene_annotations(
+                "GRCh38",
+                GeneAnnotations::default()
+                    .with_gene("BRCA1", GeneLocus::new("17", 43044294, 43125483))
+                    .with_gene("TP53", GeneLocus::new("17", 7661778, 7687538))
+                    .with_gene("PLCXD1", GeneLocus::new("X", 276321, 303356))
+                    .with_gene("PLCXD1", GeneLocus::new("Y", 276321, 303356))
+                    .with_gene("MT-CYB", GeneLocus::new("MT", 14746, 15887)),
+            )
+            .with_gene_panels(HashMap::from_iter([(
+                "hereditary-cancer".to_string(),
+                vec!["BRCA1".to_string(), "TP53".to_string()],
+            )]))
+            .with_gene_flank(100)
+            .with_default_assembly("GRCh38")
     }
 
     fn example_restrictions_manifest() -> ElsaRestrictionManifest {
         ElsaRestrictionManifest {
-            chromosome: 1,
             start: Some(1),
             end: Some(10),
+            ..restrictions_manifest_for("1")
+        }
+    }
+
+    fn restrictions_manifest_for(chromosome: &str) -> ElsaRestrictionManifest {
+        ElsaRestrictionManifest {
+            chromosome: Some(chromosome.to_string()),
+            gene: None,
+            panel: None,
+            flank: None,
+            start: None,
+            end: None,
+            exclude: vec![],
         }
     }
 }
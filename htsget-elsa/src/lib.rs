@@ -1,14 +1,25 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::result;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
+use bytes::Bytes;
 use htsget_config::resolver::Resolver;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+pub mod annotation;
+pub mod contig;
 pub mod elsa_endpoint;
+pub mod region;
 pub mod s3;
+pub mod service_auth;
+pub mod signature;
+pub mod specimen;
+pub mod storage;
 #[cfg(feature = "test-utils")]
 pub mod test_utils;
+pub mod view;
 
 pub type Result<T> = result::Result<T, Error>;
 
@@ -21,6 +32,20 @@ pub enum Error {
     InvalidReleaseUri(String),
     #[error("failed to get manifest from Elsa: `{0}`")]
     GetManifest(String),
+    #[error("invalid release key or id: `{0}`")]
+    InvalidIdentifier(String),
+    #[error("id identifies more than one file: `{0}`")]
+    AmbiguousIdentifier(String),
+    #[error("invalid authentication: `{0}`")]
+    InvalidAuthentication(String),
+    #[error("failed to authenticate with Elsa: `{0}`")]
+    ServiceAuthentication(String),
+    #[error("permission denied for release: `{0}`")]
+    PermissionDenied(String),
+    #[error("release is outside of its sharing window: `{0}`")]
+    ReleaseNotActive(String),
+    #[error("release not found in Elsa: `{0}`")]
+    ReleaseNotFound(String),
     #[error("failed to deserialize: `{0}")]
     DeserializeError(String),
     #[error("failed to serialize: `{0}")]
@@ -29,14 +54,169 @@ pub enum Error {
     GetObjectError(String),
     #[error("failed to put object into storage: `{0}`")]
     PutObjectError(String),
+    #[error("failed to delete object from storage: `{0}`")]
+    DeleteObjectError(String),
     #[error("invalid uri received from manifest: `{0}`")]
     InvalidManifest(String),
+    #[error("manifest failed verification: `{0}`")]
+    InvalidManifestSignature(String),
+    #[error("invalid gene annotations: `{0}`")]
+    InvalidAnnotation(String),
     #[error("unsupported component of manifest: `{0}`")]
     UnsupportedManifestFeature(String),
     #[error("system error: `{0}`")]
     SystemError(String),
 }
 
+/// The resolvers of a release, together with the URIs of the datasets that the release declares.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReleaseResolvers {
+    resolvers: Vec<Resolver>,
+    #[serde(default)]
+    dataset_uris: Vec<String>,
+    #[serde(default)]
+    expires_at: Option<u64>,
+    #[serde(default)]
+    ambiguous_reads: BTreeMap<String, BTreeSet<String>>,
+    #[serde(default)]
+    ambiguous_variants: BTreeMap<String, BTreeSet<String>>,
+    #[serde(default)]
+    refused_reads: BTreeSet<String>,
+    #[serde(default)]
+    refused_variants: BTreeSet<String>,
+}
+
+impl ReleaseResolvers {
+    /// Create new release resolvers.
+    pub fn new(resolvers: Vec<Resolver>, dataset_uris: Vec<String>) -> Self {
+        Self {
+            resolvers,
+            dataset_uris,
+            expires_at: None,
+            ambiguous_reads: BTreeMap::new(),
+            ambiguous_variants: BTreeMap::new(),
+            refused_reads: BTreeSet::new(),
+            refused_variants: BTreeSet::new(),
+        }
+    }
+
+    /// Set the specimen ids which identify more than one reads file, together with the ids of the
+    /// files.
+    pub fn with_ambiguous_reads(
+        mut self,
+        ambiguous_reads: BTreeMap<String, BTreeSet<String>>,
+    ) -> Self {
+        self.ambiguous_reads = ambiguous_reads;
+        self
+    }
+
+    /// Set the specimen ids which identify more than one variants file, together with the ids of
+    /// the files.
+    pub fn with_ambiguous_variants(
+        mut self,
+        ambiguous_variants: BTreeMap<String, BTreeSet<String>>,
+    ) -> Self {
+        self.ambiguous_variants = ambiguous_variants;
+        self
+    }
+
+    /// Get the ids of the reads files that an ambiguous specimen id identifies, or `None` if the id
+    /// is not ambiguous for reads.
+    pub fn ambiguous_reads(&self, id: &str) -> Option<&BTreeSet<String>> {
+        self.ambiguous_reads.get(id)
+    }
+
+    /// Get the ids of the variants files that an ambiguous specimen id identifies, or `None` if the
+    /// id is not ambiguous for variants.
+    pub fn ambiguous_variants(&self, id: &str) -> Option<&BTreeSet<String>> {
+        self.ambiguous_variants.get(id)
+    }
+
+    /// Set the ids of the reads which are refused because htsget-rs cannot serve them.
+    pub fn with_refused_reads(mut self, refused_reads: BTreeSet<String>) -> Self {
+        self.refused_reads = refused_reads;
+        self
+    }
+
+    /// Whether the id identifies reads which are refused because htsget-rs cannot serve them.
+    pub fn is_refused_read(&self, id: &str) -> bool {
+        self.refused_reads.contains(id)
+    }
+
+    /// Set the ids of the variants which are refused because they cannot be restricted to their
+    /// sample, or because htsget-rs cannot serve them.
+    pub fn with_refused_variants(mut self, refused_variants: BTreeSet<String>) -> Self {
+        self.refused_variants = refused_variants;
+        self
+    }
+
+    /// Whether the id identifies variants which are refused because they cannot be restricted to
+    /// their sample, or because htsget-rs cannot serve them.
+    pub fn is_refused_variant(&self, id: &str) -> bool {
+        self.refused_variants.contains(id)
+    }
+
+    /// Set the time, in seconds since the Unix epoch, after which the resolvers must not be used.
+    pub fn with_expires_at(mut self, expires_at: Option<u64>) -> Self {
+        self.expires_at = expires_at;
+        self
+    }
+
+    /// Get the time after which the resolvers must not be used.
+    pub fn expires_at(&self) -> Option<u64> {
+        self.expires_at
+    }
+
+    /// Whether the resolvers have expired at `now`, in seconds since the Unix epoch.
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    /// Get the resolvers.
+    pub fn resolvers(&self) -> &[Resolver] {
+        &self.resolvers
+    }
+
+    /// Get the URIs of the datasets that the release declares.
+    pub fn dataset_uris(&self) -> &[String] {
+        &self.dataset_uris
+    }
+
+    /// Convert into the resolvers.
+    pub fn into_resolvers(self) -> Vec<Resolver> {
+        self.resolvers
+    }
+}
+
+/// The revocation of a release. Each revoked release is stored as its own object, so that
+/// revoking or reinstating one release never overwrites the revocation of another. Revoked
+/// releases are refused without consulting the cache or Elsa.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Revocation {
+    revoked_at: u64,
+}
+
+impl Revocation {
+    /// Create a revocation at the time in seconds since the Unix epoch.
+    pub fn new(revoked_at: u64) -> Self {
+        Self { revoked_at }
+    }
+
+    /// Get the time that the release was revoked.
+    pub fn revoked_at(&self) -> u64 {
+        self.revoked_at
+    }
+}
+
+/// Get the current time in seconds since the Unix epoch.
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
+}
+
 /// Cache resolver objects to a cache.
 #[async_trait]
 pub trait Cache {
@@ -56,6 +236,28 @@ pub trait Cache {
         item: Self::Item,
         max_age: u64,
     ) -> result::Result<(), Self::Error>;
+
+    /// Remove all the resolvers with keys that start with the prefix from the cache.
+    async fn invalidate<K: AsRef<str> + Send + Sync>(
+        &self,
+        prefix: K,
+    ) -> result::Result<(), Self::Error>;
+
+    /// Get the revocation of the release, which is `None` if the release is not revoked.
+    async fn revocation(
+        &self,
+        release_key: &str,
+    ) -> result::Result<Option<Revocation>, Self::Error>;
+
+    /// Put the revocation of the release.
+    async fn put_revocation(
+        &self,
+        release_key: &str,
+        revocation: Revocation,
+    ) -> result::Result<(), Self::Error>;
+
+    /// Remove the revocation of the release, if there is one.
+    async fn remove_revocation(&self, release_key: &str) -> result::Result<(), Self::Error>;
 }
 
 /// Get objects from cloud storage.
@@ -69,6 +271,13 @@ pub trait GetObject {
         bucket: impl Into<String> + Send,
         key: impl Into<String> + Send,
     ) -> result::Result<T, Self::Error>;
+
+    /// Get the raw bytes of the object.
+    async fn get_object_bytes(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> result::Result<Bytes, Self::Error>;
 }
 
 /// Get resolvers from Elsa.
@@ -76,6 +285,11 @@ pub trait GetObject {
 pub trait ResolversFromElsa {
     type Error;
 
-    /// Get the resolvers from Elsa using the release key.
-    async fn try_get(&self, release_key: String) -> result::Result<Vec<Resolver>, Self::Error>;
+    /// Get the resolvers from Elsa using the release key. The authorization is the value of the
+    /// caller's `Authorization` header, which is forwarded to Elsa.
+    async fn try_get(
+        &self,
+        release_key: String,
+        authorization: Option<String>,
+    ) -> result::Result<ReleaseResolvers, Self::Error>;
 }
@@ -0,0 +1,422 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use htsget_config::resolver::{AllowGuard, ReferenceNames};
+use htsget_config::types::{Format, Interval, TaggedTypeAll};
+
+/// The bounds of an interval, where `None` represents an unbounded start or end.
+type Bounds = (Option<u32>, Option<u32>);
+
+/// The set of regions that are allowed for a single file. Each region is a reference name
+/// together with an interval on that reference.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Regions {
+    regions: BTreeMap<String, BTreeSet<Bounds>>,
+}
+
+impl Regions {
+    /// Add an allowed region. Intervals which overlap or are adjacent to the region are merged
+    /// with it, so that a query spanning them is allowed.
+    pub fn insert(&mut self, reference_name: impl Into<String>, interval: Interval) {
+        let bounds = self.regions.entry(reference_name.into()).or_default();
+
+        let mut merged = (interval.start(), interval.end());
+        bounds.retain(|allowed| {
+            let touches = !ends_before(merged.1, allowed.0) && !ends_before(allowed.1, merged.0);
+            if touches {
+                merged = (
+                    merged.0.zip(allowed.0).map(|(a, b)| a.min(b)),
+                    merged.1.zip(allowed.1).map(|(a, b)| a.max(b)),
+                );
+            }
+
+            !touches
+        });
+        bounds.insert(merged);
+    }
+
+    /// Remove an excluded interval from the allowed regions of a reference name. Allowed intervals
+    /// which overlap the excluded interval are trimmed or split around it, and a reference name with
+    /// no remaining intervals is removed.
+    pub fn remove(&mut self, reference_name: &str, interval: Interval) {
+        let Some(bounds) = self.regions.get_mut(reference_name) else {
+            return;
+        };
+
+        *bounds = bounds
+            .iter()
+            .flat_map(|allowed| subtract(*allowed, (interval.start(), interval.end())))
+            .collect();
+        if bounds.is_empty() {
+            self.regions.remove(reference_name);
+        }
+    }
+
+    /// Whether there are no allowed regions.
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// Get the allowed intervals for a reference name.
+    pub fn intervals(&self, reference_name: &str) -> Vec<Interval> {
+        self.regions
+            .get(reference_name)
+            .map(|bounds| {
+                bounds
+                    .iter()
+                    .map(|(start, end)| Interval::new(*start, *end))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Convert the regions into the allow guards that a set of resolvers for this file should use.
+    /// Reference names which share the same interval are combined into a single guard.
+    pub fn allow_guards(&self, format: Format) -> Vec<AllowGuard> {
+        let mut by_interval: BTreeMap<Bounds, BTreeSet<String>> = BTreeMap::new();
+        for (reference_name, bounds) in &self.regions {
+            for bound in bounds {
+                by_interval
+                    .entry(*bound)
+                    .or_default()
+                    .insert(reference_name.to_string());
+            }
+        }
+
+        by_interval
+            .into_iter()
+            .map(|((start, end), reference_names)| {
+                AllowGuard::default()
+                    .with_allow_formats(vec![format])
+                    .with_allow_reference_names(ReferenceNames::List(HashSet::from_iter(
+                        reference_names,
+                    )))
+                    .with_allow_interval(Interval::new(start, end))
+            })
+            .collect()
+    }
+}
+
+/// The allow guard for a file which is shared without restrictions, which allows all reference names
+/// and the whole of each reference.
+pub fn allow_all_guard(format: Format) -> AllowGuard {
+    AllowGuard::default()
+        .with_allow_formats(vec![format])
+        .with_allow_reference_names(ReferenceNames::Tagged(TaggedTypeAll::All))
+        .with_allow_interval(Interval::new(None, None))
+}
+
+/// Subtract the excluded bounds from the allowed bounds, returning the parts of the allowed bounds
+/// which remain. Bounds are half-open, so bounds which only touch do not overlap.
+fn subtract(allowed: Bounds, excluded: Bounds) -> Vec<Bounds> {
+    let ((start, end), (excluded_start, excluded_end)) = (allowed, excluded);
+    if !(is_before(excluded_start, end) && is_before(start, excluded_end)) {
+        return vec![allowed];
+    }
+
+    let mut remaining = vec![];
+    if let Some(excluded_start) = excluded_start {
+        if start.is_none_or(|start| start < excluded_start) {
+            remaining.push((start, Some(excluded_start)));
+        }
+    }
+    if let Some(excluded_end) = excluded_end {
+        if end.is_none_or(|end| excluded_end < end) {
+            remaining.push((Some(excluded_end), end));
+        }
+    }
+
+    remaining
+}
+
+/// Whether a start position is before an end position, where an unbounded start or end is always
+/// before the other.
+fn is_before(start: Option<u32>, end: Option<u32>) -> bool {
+    match (start, end) {
+        (Some(start), Some(end)) => start < end,
+        _ => true,
+    }
+}
+
+/// Whether bounds ending at `end` finish before bounds starting at `start`, so that they neither
+/// overlap nor are adjacent. Unbounded ends and starts always reach the other bounds.
+fn ends_before(end: Option<u32>, start: Option<u32>) -> bool {
+    match (end, start) {
+        (Some(end), Some(start)) => end < start,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use htsget_config::resolver::ReferenceNames::List;
+    use htsget_config::resolver::{AllowGuard, IdResolver, Resolver};
+    use htsget_config::storage::s3::S3Storage;
+    use htsget_config::storage::Storage;
+    use htsget_config::types::{Format, Interval, Query};
+
+    use crate::region::{allow_all_guard, Regions};
+
+    #[test]
+    fn regions_resolve_queries() {
+        let resolvers = example_resolvers();
+
+        for (reference_name, interval, allowed) in [
+            ("1", Interval::new(Some(150), Some(180)), true),
+            ("1", Interval::new(Some(1000), Some(1100)), true),
+            ("1", Interval::new(Some(100), Some(199)), true),
+            ("X", Interval::new(Some(150), Some(180)), true),
+            ("2", Interval::new(None, None), true),
+            ("1", Interval::new(Some(100), Some(200)), false),
+            ("1", Interval::new(Some(99), Some(180)), false),
+            ("1", Interval::new(Some(150), Some(1100)), false),
+            ("1", Interval::new(None, None), false),
+            ("X", Interval::new(Some(1000), Some(1100)), false),
+            ("3", Interval::new(Some(150), Some(180)), false),
+        ] {
+            assert_eq!(
+                resolves(&resolvers, Some(reference_name), interval),
+                allowed
+            );
+        }
+        assert!(!resolves(&resolvers, None, Interval::new(None, None)));
+    }
+
+    #[test]
+    fn regions_deduplicate() {
+        let mut regions = example_regions();
+        regions.insert("1", Interval::new(Some(100), Some(200)));
+
+        assert_eq!(regions, example_regions());
+        assert_eq!(regions.intervals("1").len(), 2);
+    }
+
+    #[test]
+    fn regions_allow_guards() {
+        let resolvers = example_resolvers();
+
+        assert_eq!(resolvers.len(), 3);
+        assert!(resolvers
+            .iter()
+            .all(|resolver| resolver.allow_formats() == [Format::Bam]));
+        assert!(resolvers.iter().any(|resolver| {
+            resolver.allow_reference_names()
+                == &List(HashSet::from_iter(vec!["1".to_string(), "X".to_string()]))
+                && resolver.allow_interval() == Interval::new(Some(100), Some(200))
+        }));
+        assert!(resolvers.iter().any(|resolver| {
+            resolver.allow_reference_names() == &List(HashSet::from_iter(vec!["1".to_string()]))
+                && resolver.allow_interval() == Interval::new(Some(1000), Some(2000))
+        }));
+        assert!(resolvers.iter().any(|resolver| {
+            resolver.allow_reference_names() == &List(HashSet::from_iter(vec!["2".to_string()]))
+                && resolver.allow_interval() == Interval::new(None, None)
+        }));
+    }
+
+    #[test]
+    fn regions_remove_splits_interval() {
+        let mut regions = example_regions();
+        regions.remove("1", Interval::new(Some(120), Some(150)));
+
+        assert_eq!(
+            regions.intervals("1"),
+            vec![
+                Interval::new(Some(100), Some(120)),
+                Interval::new(Some(150), Some(200)),
+                Interval::new(Some(1000), Some(2000)),
+            ]
+        );
+        assert_eq!(
+            regions.intervals("X"),
+            vec![Interval::new(Some(100), Some(200))]
+        );
+    }
+
+    #[test]
+    fn regions_remove_trims_interval() {
+        let mut regions = example_regions();
+        regions.remove("1", Interval::new(Some(50), Some(150)));
+        regions.remove("1", Interval::new(Some(1500), Some(2500)));
+
+        assert_eq!(
+            regions.intervals("1"),
+            vec![
+                Interval::new(Some(150), Some(200)),
+                Interval::new(Some(1000), Some(1500)),
+            ]
+        );
+    }
+
+    #[test]
+    fn regions_remove_across_intervals() {
+        let mut regions = example_regions();
+        regions.remove("1", Interval::new(Some(150), Some(1500)));
+
+        assert_eq!(
+            regions.intervals("1"),
+            vec![
+                Interval::new(Some(100), Some(150)),
+                Interval::new(Some(1500), Some(2000)),
+            ]
+        );
+    }
+
+    #[test]
+    fn regions_remove_adjacent_interval() {
+        let mut regions = example_regions();
+        regions.remove("1", Interval::new(Some(200), Some(1000)));
+        regions.remove("1", Interval::new(None, Some(100)));
+        regions.remove("1", Interval::new(Some(2000), None));
+
+        assert_eq!(regions, example_regions());
+    }
+
+    #[test]
+    fn regions_remove_unbounded() {
+        let mut regions = example_regions();
+        regions.remove("2", Interval::new(Some(100), Some(200)));
+        assert_eq!(
+            regions.intervals("2"),
+            vec![
+                Interval::new(None, Some(100)),
+                Interval::new(Some(200), None),
+            ]
+        );
+
+        regions.remove("2", Interval::new(None, Some(150)));
+        regions.remove("1", Interval::new(Some(150), None));
+        assert_eq!(regions.intervals("2"), vec![Interval::new(Some(200), None)]);
+        assert_eq!(
+            regions.intervals("1"),
+            vec![Interval::new(Some(100), Some(150))]
+        );
+    }
+
+    #[test]
+    fn regions_remove_whole_reference_name() {
+        let mut regions = example_regions();
+        regions.remove("X", Interval::new(Some(100), Some(200)));
+        regions.remove("2", Interval::new(None, None));
+        regions.remove("3", Interval::new(None, None));
+
+        assert!(regions.intervals("X").is_empty());
+        assert!(regions.intervals("2").is_empty());
+        assert!(!resolves(
+            &resolvers(regions.allow_guards(Format::Bam)),
+            Some("2"),
+            Interval::new(Some(1), Some(2))
+        ));
+        assert_eq!(regions.allow_guards(Format::Bam).len(), 2);
+
+        regions.remove("1", Interval::new(None, None));
+        assert!(regions.is_empty());
+    }
+
+    #[test]
+    fn allow_all_guard_allows_any_region() {
+        let resolvers = resolvers(vec![allow_all_guard(Format::Bam)]);
+
+        assert_eq!(resolvers.first().unwrap().allow_formats(), [Format::Bam]);
+        assert!(resolves(&resolvers, None, Interval::new(None, None)));
+        assert!(resolves(
+            &resolvers,
+            Some("chr7"),
+            Interval::new(Some(100), Some(200))
+        ));
+    }
+
+    fn example_resolvers() -> Vec<Resolver> {
+        resolvers(example_regions().allow_guards(Format::Bam))
+    }
+
+    fn resolvers(guards: Vec<AllowGuard>) -> Vec<Resolver> {
+        guards
+            .into_iter()
+            .map(|guard| {
+                Resolver::new(
+                    Storage::S3 {
+                        s3_storage: S3Storage::new("bucket".to_string(), None, false),
+                    },
+                    "^R004/id$",
+                    "key",
+                    guard,
+                )
+                .unwrap()
+            })
+            .collect()
+    }
+
+    /// Check whether htsget-rs resolves a query for the region using the resolvers.
+    fn resolves(resolvers: &[Resolver], reference_name: Option<&str>, interval: Interval) -> bool {
+        let mut query = Query::new_with_default_request("R004/id", Format::Bam);
+        if let Some(reference_name) = reference_name {
+            query = query.with_reference_name(reference_name);
+        }
+        if let Some(start) = interval.start() {
+            query = query.with_start(start);
+        }
+        if let Some(end) = interval.end() {
+            query = query.with_end(end);
+        }
+
+        resolvers.resolve_id(&query).is_some()
+    }
+
+    #[test]
+    fn regions_merge_overlapping_and_adjacent() {
+        let mut regions = example_regions();
+        regions.insert("1", Interval::new(Some(150), Some(300)));
+        regions.insert("1", Interval::new(Some(300), Some(400)));
+        regions.insert("1", Interval::new(Some(900), Some(1000)));
+        regions.insert("X", Interval::new(Some(201), Some(300)));
+
+        assert_eq!(
+            regions.intervals("1"),
+            vec![
+                Interval::new(Some(100), Some(400)),
+                Interval::new(Some(900), Some(2000))
+            ]
+        );
+        assert_eq!(
+            regions.intervals("X"),
+            vec![
+                Interval::new(Some(100), Some(200)),
+                Interval::new(Some(201), Some(300))
+            ]
+        );
+        assert!(resolves(
+            &resolvers(regions.allow_guards(Format::Bam)),
+            Some("1"),
+            Interval::new(Some(150), Some(350))
+        ));
+    }
+
+    #[test]
+    fn regions_merge_unbounded() {
+        let mut regions = example_regions();
+        regions.insert("1", Interval::new(None, Some(100)));
+        regions.insert("1", Interval::new(Some(1500), None));
+
+        assert_eq!(
+            regions.intervals("1"),
+            vec![
+                Interval::new(None, Some(200)),
+                Interval::new(Some(1000), None)
+            ]
+        );
+
+        regions.insert("1", Interval::new(Some(200), Some(1000)));
+        assert_eq!(regions.intervals("1"), vec![Interval::new(None, None)]);
+    }
+
+    fn example_regions() -> Regions {
+        let mut regions = Regions::default();
+        regions.insert("1", Interval::new(Some(100), Some(200)));
+        regions.insert("1", Interval::new(Some(1000), Some(2000)));
+        regions.insert("X", Interval::new(Some(100), Some(200)));
+        regions.insert("2", Interval::new(None, None));
+        regions
+    }
+}
@@ -2,16 +2,26 @@ use std::ops::Sub;
 use std::time::{Duration, SystemTime};
 
 use async_trait::async_trait;
+use aws_sdk_s3::operation::get_object::GetObjectError as S3GetObjectError;
 use aws_sdk_s3::primitives::{ByteStream, DateTime};
 use aws_sdk_s3::Client;
 use bytes::Bytes;
-use htsget_config::resolver::Resolver;
 use serde::{Deserialize, Serialize};
 use serde_json::{from_slice, to_vec};
 use tracing::{instrument, trace};
 
-use crate::Error::{DeserializeError, GetObjectError, PutObjectError, SerializeError};
-use crate::{Cache, Error, GetObject, Result};
+use crate::Error::{
+    DeleteObjectError, DeserializeError, GetObjectError, PutObjectError, SerializeError,
+};
+use crate::{unix_now, Cache, Error, GetObject, ReleaseResolvers, Result, Revocation};
+
+/// The prefix of the revocation objects in the cache bucket.
+pub const REVOCATION_PREFIX: &str = "htsget-manifest-revocations";
+
+/// Get the key of the revocation object for the release.
+pub fn revocation_key(release_key: &str) -> String {
+    format!("{REVOCATION_PREFIX}/{release_key}")
+}
 
 /// S3 storage implementation.
 #[derive(Debug)]
@@ -23,7 +33,7 @@ pub struct S3 {
 /// The shape of the item to cache.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct CacheItem {
-    item: Vec<Resolver>,
+    item: ReleaseResolvers,
     max_age: u64,
 }
 
@@ -65,26 +75,137 @@ impl S3 {
 
     /// Execute a get object request.
     #[instrument(level = "trace", skip_all)]
+    async fn get_object_bytes(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> Result<Bytes> {
+        Ok(self
+            .s3_client
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|err| GetObjectError(err.to_string()))?
+            .body
+            .collect()
+            .await
+            .map_err(|err| GetObjectError(err.to_string()))?
+            .into_bytes())
+    }
+
+    /// Execute a get object request and deserialize the object.
+    #[instrument(level = "trace", skip_all)]
     async fn get_object<T: for<'de> Deserialize<'de>>(
         &self,
         bucket: impl Into<String> + Send,
         key: impl Into<String> + Send,
     ) -> Result<T> {
-        let output = self
+        let output = self.get_object_bytes(bucket, key).await?;
+
+        from_slice(output.as_ref()).map_err(|err| DeserializeError(err.to_string()))
+    }
+
+    /// Execute a get object request and deserialize the object, returning `None` if the object does
+    /// not exist. Any other error is returned rather than treated as a missing object.
+    #[instrument(level = "trace", skip_all)]
+    async fn get_object_if_exists<T: for<'de> Deserialize<'de>>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> Result<Option<T>> {
+        let output = match self
             .s3_client
             .get_object()
             .bucket(bucket)
             .key(key)
             .send()
             .await
-            .map_err(|err| GetObjectError(err.to_string()))?
+        {
+            Ok(output) => output,
+            Err(err) => {
+                return match err.into_service_error() {
+                    S3GetObjectError::NoSuchKey(_) => Ok(None),
+                    err => Err(GetObjectError(err.to_string())),
+                }
+            }
+        };
+
+        let bytes = output
             .body
             .collect()
             .await
             .map_err(|err| GetObjectError(err.to_string()))?
             .into_bytes();
 
-        from_slice(output.as_ref()).map_err(|err| DeserializeError(err.to_string()))
+        from_slice(bytes.as_ref())
+            .map(Some)
+            .map_err(|err| DeserializeError(err.to_string()))
+    }
+
+    /// Execute a put object request with the serialized value.
+    #[instrument(level = "trace", skip_all)]
+    async fn put_object<T: Serialize>(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+        value: &T,
+    ) -> Result<()> {
+        self.s3_client
+            .put_object()
+            .bucket(bucket)
+            .key(key)
+            .body(ByteStream::from(Bytes::from(
+                to_vec(value).map_err(|err| SerializeError(err.to_string()))?,
+            )))
+            .send()
+            .await
+            .map_err(|err| {
+                let err = err.into_service_error().to_string();
+                trace!(err = err, "put object error");
+
+                PutObjectError(err)
+            })?;
+
+        Ok(())
+    }
+
+    /// List the keys of all the objects in the bucket that start with the prefix.
+    #[instrument(level = "trace", skip_all)]
+    async fn list_keys(
+        &self,
+        bucket: impl Into<String> + Send,
+        prefix: impl Into<String> + Send,
+    ) -> Result<Vec<String>> {
+        let (bucket, prefix) = (bucket.into(), prefix.into());
+
+        let mut keys = vec![];
+        let mut continuation_token = None;
+        loop {
+            let output = self
+                .s3_client
+                .list_objects_v2()
+                .bucket(&bucket)
+                .prefix(&prefix)
+                .set_continuation_token(continuation_token)
+                .send()
+                .await
+                .map_err(|err| GetObjectError(err.into_service_error().to_string()))?;
+
+            keys.extend(
+                output
+                    .contents()
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|object| object.key().map(str::to_string)),
+            );
+
+            match output.next_continuation_token() {
+                Some(token) => continuation_token = Some(token.to_string()),
+                None => return Ok(keys),
+            }
+        }
     }
 }
 
@@ -99,12 +220,20 @@ impl GetObject for S3 {
     ) -> Result<T> {
         Ok(self.get_object(bucket, key).await?)
     }
+
+    async fn get_object_bytes(
+        &self,
+        bucket: impl Into<String> + Send,
+        key: impl Into<String> + Send,
+    ) -> Result<Bytes> {
+        Ok(self.get_object_bytes(bucket, key).await?)
+    }
 }
 
 #[async_trait]
 impl Cache for S3 {
     type Error = Error;
-    type Item = Vec<Resolver>;
+    type Item = ReleaseResolvers;
 
     #[instrument(level = "trace", skip_all, ret)]
     async fn get<K: AsRef<str> + Send + Sync>(&self, key: K) -> Result<Option<Self::Item>> {
@@ -114,6 +243,11 @@ impl Cache for S3 {
             if let Some(last_modified) = self.last_modified(cache_bucket, key.as_ref()).await {
                 let object: CacheItem = self.get_object(cache_bucket, key.as_ref()).await?;
 
+                if object.item.is_expired(unix_now()) {
+                    trace!("cached resolvers are outside of their sharing window");
+                    return Ok(None);
+                }
+
                 if last_modified.as_nanos()
                     > DateTime::from(SystemTime::now().sub(Duration::from_secs(object.max_age)))
                         .as_nanos()
@@ -137,23 +271,74 @@ impl Cache for S3 {
     ) -> Result<()> {
         trace!(key = key.as_ref(), "putting key");
 
+        if let Some(cache_bucket) = &self.cache_bucket {
+            self.put_object(cache_bucket, key.as_ref(), &CacheItem { item, max_age })
+                .await?;
+        } else {
+            trace!("no caching bucket configured");
+        }
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn invalidate<K: AsRef<str> + Send + Sync>(&self, prefix: K) -> Result<()> {
+        trace!(prefix = prefix.as_ref(), "invalidating prefix");
+
+        if let Some(cache_bucket) = &self.cache_bucket {
+            for key in self.list_keys(cache_bucket, prefix.as_ref()).await? {
+                self.s3_client
+                    .delete_object()
+                    .bucket(cache_bucket)
+                    .key(&key)
+                    .send()
+                    .await
+                    .map_err(|err| DeleteObjectError(err.into_service_error().to_string()))?;
+            }
+        } else {
+            trace!("no caching bucket configured");
+        }
+
+        Ok(())
+    }
+
+    #[instrument(level = "trace", skip_all, ret)]
+    async fn revocation(&self, release_key: &str) -> Result<Option<Revocation>> {
+        match &self.cache_bucket {
+            Some(cache_bucket) => {
+                self.get_object_if_exists(cache_bucket, revocation_key(release_key))
+                    .await
+            }
+            None => {
+                trace!("no caching bucket configured");
+                Ok(None)
+            }
+        }
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn put_revocation(&self, release_key: &str, revocation: Revocation) -> Result<()> {
+        match &self.cache_bucket {
+            Some(cache_bucket) => {
+                self.put_object(cache_bucket, revocation_key(release_key), &revocation)
+                    .await
+            }
+            None => Err(PutObjectError(
+                "no caching bucket configured for revocations".to_string(),
+            )),
+        }
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn remove_revocation(&self, release_key: &str) -> Result<()> {
         if let Some(cache_bucket) = &self.cache_bucket {
             self.s3_client
-                .put_object()
+                .delete_object()
                 .bucket(cache_bucket)
-                .key(key.as_ref())
-                .body(ByteStream::from(Bytes::from(
-                    to_vec(&CacheItem { item, max_age })
-                        .map_err(|err| SerializeError(err.to_string()))?,
-                )))
+                .key(revocation_key(release_key))
                 .send()
                 .await
-                .map_err(|err| {
-                    let err = err.into_service_error().to_string();
-                    trace!(err = err, "put object error");
-
-                    PutObjectError(err)
-                })?;
+                .map_err(|err| DeleteObjectError(err.into_service_error().to_string()))?;
         } else {
             trace!("no caching bucket configured");
         }
@@ -169,9 +354,10 @@ mod tests {
     use serde_json::{from_str, to_string};
 
     use crate::elsa_endpoint::ElsaManifest;
-    use crate::s3::{CacheItem, S3};
+    use crate::s3::{revocation_key, CacheItem, S3};
     use crate::test_utils::{example_elsa_manifest, with_test_mocks, write_example_manifest};
-    use crate::Cache;
+    use crate::Error::{GetObjectError, PutObjectError};
+    use crate::{Cache, ReleaseResolvers, Revocation};
 
     #[tokio::test]
     async fn last_modified() {
@@ -261,7 +447,7 @@ mod tests {
                 fs::write(
                     manifest_path.join("R004"),
                     to_string(&CacheItem {
-                        item: vec![],
+                        item: ReleaseResolvers::default(),
                         max_age: 1000,
                     })
                     .unwrap(),
@@ -287,7 +473,7 @@ mod tests {
                 fs::write(
                     manifest_path.join("R004"),
                     to_string(&CacheItem {
-                        item: vec![],
+                        item: ReleaseResolvers::default(),
                         max_age: 0,
                     })
                     .unwrap(),
@@ -302,6 +488,32 @@ mod tests {
         .await;
     }
 
+    #[tokio::test]
+    async fn get_cache_outside_sharing_window() {
+        with_test_mocks(
+            |_, s3_client, _, base_path| async move {
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+
+                let manifest_path = base_path.join("elsa-data-tmp/htsget-manifests");
+                fs::create_dir_all(&manifest_path).unwrap();
+                fs::write(
+                    manifest_path.join("R004"),
+                    to_string(&CacheItem {
+                        item: ReleaseResolvers::default().with_expires_at(Some(1)),
+                        max_age: 1000,
+                    })
+                    .unwrap(),
+                )
+                .unwrap();
+
+                let result = s3.get("htsget-manifests/R004").await;
+                assert!(matches!(result, Ok(None)));
+            },
+            0,
+        )
+        .await;
+    }
+
     #[tokio::test]
     async fn get() {
         with_test_mocks(
@@ -313,7 +525,7 @@ mod tests {
                 fs::write(
                     manifest_path.join("R004"),
                     to_string(&CacheItem {
-                        item: vec![],
+                        item: ReleaseResolvers::default(),
                         max_age: 1000,
                     })
                     .unwrap(),
@@ -321,7 +533,7 @@ mod tests {
                 .unwrap();
 
                 let result = s3.get("htsget-manifests/R004").await.unwrap().unwrap();
-                assert!(result.is_empty());
+                assert!(result.resolvers().is_empty());
             },
             0,
         )
@@ -337,18 +549,125 @@ mod tests {
                 let manifest_path = base_path.join("elsa-data-tmp");
                 fs::create_dir_all(&manifest_path).unwrap();
 
-                s3.put("htsget-manifests/R004", vec![], 1000).await.unwrap();
+                s3.put("htsget-manifests/R004", ReleaseResolvers::default(), 1000)
+                    .await
+                    .unwrap();
 
                 let result: CacheItem = from_str(
                     &fs::read_to_string(manifest_path.join("htsget-manifests/R004")).unwrap(),
                 )
                 .unwrap();
 
-                assert!(result.item.is_empty());
+                assert!(result.item.resolvers().is_empty());
                 assert_eq!(result.max_age, 1000);
             },
             0,
         )
         .await;
     }
+
+    #[tokio::test]
+    async fn invalidate() {
+        with_test_mocks(
+            |_, s3_client, _, base_path| async move {
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+
+                fs::create_dir_all(base_path.join("elsa-data-tmp")).unwrap();
+                for key in ["cache/R004/a", "cache/R004/b", "cache/R005/a"] {
+                    s3.put(key, ReleaseResolvers::default(), 1000)
+                        .await
+                        .unwrap();
+                }
+
+                s3.invalidate("cache/R004/").await.unwrap();
+
+                assert!(!base_path.join("elsa-data-tmp/cache/R004/a").exists());
+                assert!(!base_path.join("elsa-data-tmp/cache/R004/b").exists());
+                assert!(base_path.join("elsa-data-tmp/cache/R005/a").exists());
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn revocation() {
+        with_test_mocks(
+            |_, s3_client, _, base_path| async move {
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+
+                fs::create_dir_all(base_path.join("elsa-data-tmp")).unwrap();
+                assert_eq!(s3.revocation("R004").await.unwrap(), None);
+
+                s3.put_revocation("R004", Revocation::new(1000))
+                    .await
+                    .unwrap();
+                s3.put_revocation("R005", Revocation::new(2000))
+                    .await
+                    .unwrap();
+
+                assert!(base_path
+                    .join(format!("elsa-data-tmp/{}", revocation_key("R004")))
+                    .exists());
+                assert_eq!(
+                    s3.revocation("R004").await.unwrap(),
+                    Some(Revocation::new(1000))
+                );
+                assert_eq!(
+                    s3.revocation("R005").await.unwrap(),
+                    Some(Revocation::new(2000))
+                );
+
+                s3.remove_revocation("R004").await.unwrap();
+                assert_eq!(s3.revocation("R004").await.unwrap(), None);
+                assert_eq!(
+                    s3.revocation("R005").await.unwrap(),
+                    Some(Revocation::new(2000))
+                );
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn revocation_fails_closed() {
+        with_test_mocks(
+            |_, s3_client, _, base_path| async move {
+                let s3 = S3::new(s3_client, Some("elsa-data-tmp".to_string()));
+
+                // A directory at the key cannot be read as an object, which is an error other than
+                // the object not existing.
+                fs::create_dir_all(
+                    base_path
+                        .join("elsa-data-tmp")
+                        .join(revocation_key("R004"))
+                        .join("object"),
+                )
+                .unwrap();
+                let result = s3.revocation("R004").await;
+                assert!(matches!(result, Err(GetObjectError(_))));
+            },
+            0,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn revocation_no_cache_bucket() {
+        with_test_mocks(
+            |_, s3_client, _, _| async move {
+                let s3 = S3::new(s3_client, None);
+
+                assert_eq!(s3.revocation("R004").await.unwrap(), None);
+                assert!(matches!(
+                    s3.put_revocation("R004", Revocation::new(1000)).await,
+                    Err(PutObjectError(_))
+                ));
+                s3.remove_revocation("R004").await.unwrap();
+            },
+            0,
+        )
+        .await;
+    }
 }
@@ -0,0 +1,344 @@
+use std::env;
+use std::fmt::{Debug, Formatter};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, instrument};
+
+use crate::Error::ServiceAuthentication;
+use crate::Result;
+
+/// The header used to forward the caller's authorization to Elsa when htsget-elsa authenticates
+/// itself using the `Authorization` header.
+pub const FORWARDED_AUTHORIZATION: &str = "x-forwarded-authorization";
+
+/// How long before its expiry that a client credentials token is refreshed.
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// Configuration for how htsget-elsa authenticates itself to Elsa.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type")]
+pub enum ServiceAuthConfig {
+    /// A static bearer token, read from an environment variable.
+    Bearer { token_env: String },
+    /// The OAuth2 client credentials flow, with the client secret read from an environment variable.
+    ClientCredentials {
+        token_url: String,
+        client_id: String,
+        client_secret_env: String,
+        scope: Option<String>,
+    },
+}
+
+/// A token from the OAuth2 token endpoint.
+#[derive(Deserialize, Debug)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+/// A cached client credentials token.
+#[derive(Debug, Clone)]
+struct CachedToken {
+    access_token: String,
+    refresh_at: Instant,
+}
+
+/// The OAuth2 client credentials flow, which caches its token until it is about to expire. Tokens
+/// without an `expires_in` are not cached, because it is not known when they expire.
+pub struct ClientCredentials {
+    token_url: String,
+    client_id: String,
+    client_secret: String,
+    scope: Option<String>,
+    token: Mutex<Option<CachedToken>>,
+}
+
+impl ClientCredentials {
+    /// Create new client credentials.
+    pub fn new(
+        token_url: String,
+        client_id: String,
+        client_secret: String,
+        scope: Option<String>,
+    ) -> Self {
+        Self {
+            token_url,
+            client_id,
+            client_secret,
+            scope,
+            token: Mutex::new(None),
+        }
+    }
+
+    /// Get the cached token if it is not about to expire.
+    fn cached_token(&self) -> Option<String> {
+        self.token
+            .lock()
+            .ok()?
+            .as_ref()
+            .filter(|token| Instant::now() < token.refresh_at)
+            .map(|token| token.access_token.to_string())
+    }
+
+    /// Clear the cached token, so that the next request gets a new one from the token endpoint.
+    fn clear_token(&self) {
+        if let Ok(mut cached) = self.token.lock() {
+            *cached = None;
+        }
+    }
+
+    /// Get a token, requesting a new one from the token endpoint if the cached token is about to expire.
+    #[instrument(level = "debug", skip_all)]
+    async fn access_token(&self, client: &Client) -> Result<String> {
+        if let Some(access_token) = self.cached_token() {
+            return Ok(access_token);
+        }
+
+        debug!(token_url = %self.token_url, "requesting client credentials token");
+
+        let mut form = vec![
+            ("grant_type", "client_credentials"),
+            ("client_id", self.client_id.as_str()),
+            ("client_secret", self.client_secret.as_str()),
+        ];
+        if let Some(scope) = &self.scope {
+            form.push(("scope", scope.as_str()));
+        }
+
+        let response = client
+            .post(&self.token_url)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|err| ServiceAuthentication(err.to_string()))?;
+        if !response.status().is_success() {
+            return Err(ServiceAuthentication(response.status().to_string()));
+        }
+
+        let token: TokenResponse = response
+            .json()
+            .await
+            .map_err(|err| ServiceAuthentication(err.to_string()))?;
+
+        if let Ok(mut cached) = self.token.lock() {
+            *cached = token.expires_in.map(|expires_in| CachedToken {
+                access_token: token.access_token.to_string(),
+                refresh_at: Instant::now()
+                    + Duration::from_secs(expires_in).saturating_sub(REFRESH_MARGIN),
+            });
+        }
+
+        Ok(token.access_token)
+    }
+}
+
+/// Authenticates htsget-elsa to Elsa.
+pub enum ServiceAuth {
+    /// A static bearer token.
+    Bearer(String),
+    /// The OAuth2 client credentials flow.
+    ClientCredentials(ClientCredentials),
+}
+
+impl Debug for ServiceAuth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServiceAuth::Bearer(_) => f.write_str("Bearer"),
+            ServiceAuth::ClientCredentials(credentials) => f
+                .debug_struct("ClientCredentials")
+                .field("token_url", &credentials.token_url)
+                .field("client_id", &credentials.client_id)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+impl ServiceAuth {
+    /// Create the service auth from the config, reading any secrets from the environment.
+    pub fn from_config(config: &ServiceAuthConfig) -> Result<Self> {
+        Ok(match config {
+            ServiceAuthConfig::Bearer { token_env } => Self::Bearer(read_env(token_env)?),
+            ServiceAuthConfig::ClientCredentials {
+                token_url,
+                client_id,
+                client_secret_env,
+                scope,
+            } => Self::ClientCredentials(ClientCredentials::new(
+                token_url.to_string(),
+                client_id.to_string(),
+                read_env(client_secret_env)?,
+                scope.clone(),
+            )),
+        })
+    }
+
+    /// Get the value of the `Authorization` header to send to Elsa.
+    pub async fn authorization(&self, client: &Client) -> Result<String> {
+        let token = match self {
+            ServiceAuth::Bearer(token) => token.to_string(),
+            ServiceAuth::ClientCredentials(credentials) => credentials.access_token(client).await?,
+        };
+
+        Ok(format!("Bearer {token}"))
+    }
+
+    /// Clear any cached token, for example after Elsa rejects it.
+    pub fn invalidate(&self) {
+        if let ServiceAuth::ClientCredentials(credentials) = self {
+            credentials.clear_token();
+        }
+    }
+}
+
+fn read_env(name: &str) -> Result<String> {
+    env::var(name).map_err(|err| ServiceAuthentication(format!("failed to read `{name}`: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use serde_json::json;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::service_auth::{ServiceAuth, ServiceAuthConfig};
+    use crate::Error::ServiceAuthentication;
+
+    #[tokio::test]
+    async fn bearer_from_env() {
+        env::set_var("HTSGET_ELSA_TEST_BEARER_TOKEN", "token");
+
+        let service_auth = ServiceAuth::from_config(&ServiceAuthConfig::Bearer {
+            token_env: "HTSGET_ELSA_TEST_BEARER_TOKEN".to_string(),
+        })
+        .unwrap();
+
+        assert_eq!(
+            service_auth
+                .authorization(&reqwest::Client::new())
+                .await
+                .unwrap(),
+            "Bearer token"
+        );
+    }
+
+    #[test]
+    fn bearer_missing_env() {
+        let service_auth = ServiceAuth::from_config(&ServiceAuthConfig::Bearer {
+            token_env: "HTSGET_ELSA_TEST_MISSING_TOKEN".to_string(),
+        });
+
+        assert!(matches!(service_auth, Err(ServiceAuthentication(_))));
+    }
+
+    #[tokio::test]
+    async fn client_credentials_cached() {
+        let server = MockServer::start().await;
+        mount_token_endpoint(&server, 3600, 1).await;
+
+        let service_auth = client_credentials(&server);
+        let client = reqwest::Client::new();
+
+        assert_eq!(
+            service_auth.authorization(&client).await.unwrap(),
+            "Bearer service"
+        );
+        assert_eq!(
+            service_auth.authorization(&client).await.unwrap(),
+            "Bearer service"
+        );
+    }
+
+    #[tokio::test]
+    async fn client_credentials_refreshed() {
+        let server = MockServer::start().await;
+        mount_token_endpoint(&server, 30, 2).await;
+
+        let service_auth = client_credentials(&server);
+        let client = reqwest::Client::new();
+
+        service_auth.authorization(&client).await.unwrap();
+        service_auth.authorization(&client).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn client_credentials_without_expiry() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "service",
+                "token_type": "Bearer",
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let service_auth = client_credentials(&server);
+        let client = reqwest::Client::new();
+
+        service_auth.authorization(&client).await.unwrap();
+        service_auth.authorization(&client).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn client_credentials_invalidated() {
+        let server = MockServer::start().await;
+        mount_token_endpoint(&server, 3600, 2).await;
+
+        let service_auth = client_credentials(&server);
+        let client = reqwest::Client::new();
+
+        service_auth.authorization(&client).await.unwrap();
+        service_auth.invalidate();
+        service_auth.authorization(&client).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn client_credentials_rejected() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        let response = client_credentials(&server)
+            .authorization(&reqwest::Client::new())
+            .await;
+
+        assert!(matches!(response, Err(ServiceAuthentication(_))));
+    }
+
+    /// Mount a token endpoint which returns tokens that expire in `expires_in` seconds.
+    async fn mount_token_endpoint(server: &MockServer, expires_in: u64, expect_times: u64) {
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .and(body_string_contains("grant_type=client_credentials"))
+            .and(body_string_contains("client_secret=secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "service",
+                "token_type": "Bearer",
+                "expires_in": expires_in,
+            })))
+            .expect(expect_times)
+            .mount(server)
+            .await;
+    }
+
+    fn client_credentials(server: &MockServer) -> ServiceAuth {
+        env::set_var("HTSGET_ELSA_TEST_CLIENT_SECRET", "secret");
+
+        ServiceAuth::from_config(&ServiceAuthConfig::ClientCredentials {
+            token_url: format!("{}/oauth/token", server.uri()),
+            client_id: "htsget-elsa".to_string(),
+            client_secret_env: "HTSGET_ELSA_TEST_CLIENT_SECRET".to_string(),
+            scope: Some("elsa.manifest".to_string()),
+        })
+        .unwrap()
+    }
+}
@@ -0,0 +1,154 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use sha2::{Digest, Sha256};
+
+use crate::Error::InvalidManifestSignature;
+use crate::Result;
+
+/// The length of an Ed25519 public key.
+const ED25519_PUBLIC_KEY_LEN: usize = 32;
+
+/// Check that the SHA-256 hash of the manifest matches the hex encoded hash from Elsa.
+pub fn verify_sha256(manifest: &[u8], sha256: &str) -> Result<()> {
+    if hex::encode(Sha256::digest(manifest)).eq_ignore_ascii_case(sha256) {
+        Ok(())
+    } else {
+        Err(InvalidManifestSignature(
+            "manifest hash does not match".to_string(),
+        ))
+    }
+}
+
+/// Verifies detached Ed25519 signatures of manifests using the public key of Elsa.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestVerifier {
+    public_key: Vec<u8>,
+}
+
+impl ManifestVerifier {
+    /// Create a verifier from a raw Ed25519 public key.
+    pub fn new(public_key: Vec<u8>) -> Result<Self> {
+        if public_key.len() != ED25519_PUBLIC_KEY_LEN {
+            return Err(InvalidManifestSignature(format!(
+                "public key must be {ED25519_PUBLIC_KEY_LEN} bytes"
+            )));
+        }
+
+        Ok(Self { public_key })
+    }
+
+    /// Create a verifier from a base64 encoded raw Ed25519 public key.
+    pub fn from_base64(public_key: &str) -> Result<Self> {
+        Self::new(
+            STANDARD
+                .decode(public_key.trim())
+                .map_err(|err| InvalidManifestSignature(err.to_string()))?,
+        )
+    }
+
+    /// Verify the base64 encoded signature of the manifest. A missing signature is rejected.
+    pub fn verify(&self, manifest: &[u8], signature: Option<&str>) -> Result<()> {
+        let signature = signature
+            .ok_or_else(|| InvalidManifestSignature("manifest is not signed".to_string()))
+            .and_then(|signature| {
+                STANDARD
+                    .decode(signature)
+                    .map_err(|err| InvalidManifestSignature(err.to_string()))
+            })?;
+
+        UnparsedPublicKey::new(&ED25519, &self.public_key)
+            .verify(manifest, &signature)
+            .map_err(|_| InvalidManifestSignature("signature does not match manifest".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use sha2::{Digest, Sha256};
+
+    use crate::signature::{verify_sha256, ManifestVerifier};
+    use crate::Error::InvalidManifestSignature;
+
+    const MANIFEST: &[u8] = br#"{ "id": "R004", "reads": {}, "variants": {} }"#;
+
+    #[test]
+    fn verify_signed_manifest() {
+        let key_pair = key_pair();
+        let verifier = verifier(&key_pair);
+
+        let signature = STANDARD.encode(key_pair.sign(MANIFEST));
+
+        assert!(verifier.verify(MANIFEST, Some(&signature)).is_ok());
+    }
+
+    #[test]
+    fn verify_tampered_manifest() {
+        let key_pair = key_pair();
+        let verifier = verifier(&key_pair);
+
+        let signature = STANDARD.encode(key_pair.sign(MANIFEST));
+        let tampered = br#"{ "id": "R004", "reads": {}, "variants": {}, "x": 1 }"#;
+
+        assert!(matches!(
+            verifier.verify(tampered, Some(&signature)),
+            Err(InvalidManifestSignature(_))
+        ));
+    }
+
+    #[test]
+    fn verify_other_key() {
+        let verifier = verifier(&key_pair());
+
+        let signature = STANDARD.encode(key_pair().sign(MANIFEST));
+
+        assert!(matches!(
+            verifier.verify(MANIFEST, Some(&signature)),
+            Err(InvalidManifestSignature(_))
+        ));
+    }
+
+    #[test]
+    fn verify_unsigned_manifest() {
+        let verifier = verifier(&key_pair());
+
+        assert!(matches!(
+            verifier.verify(MANIFEST, None),
+            Err(InvalidManifestSignature(_))
+        ));
+        assert!(matches!(
+            verifier.verify(MANIFEST, Some("not base64!")),
+            Err(InvalidManifestSignature(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_public_key() {
+        assert!(matches!(
+            ManifestVerifier::from_base64(&STANDARD.encode([0; 16])),
+            Err(InvalidManifestSignature(_))
+        ));
+    }
+
+    #[test]
+    fn sha256_hash() {
+        assert!(matches!(
+            verify_sha256(MANIFEST, &"0".repeat(64)),
+            Err(InvalidManifestSignature(_))
+        ));
+        assert!(verify_sha256(MANIFEST, &hex::encode(Sha256::digest(MANIFEST))).is_ok());
+    }
+
+    fn key_pair() -> Ed25519KeyPair {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+    }
+
+    fn verifier(key_pair: &Ed25519KeyPair) -> ManifestVerifier {
+        ManifestVerifier::from_base64(&STANDARD.encode(key_pair.public_key().as_ref())).unwrap()
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::elsa_endpoint::validate_identifier;
+
+/// A case in the manifest, which groups the patients of a release.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ElsaCaseManifest {
+    #[serde(default)]
+    ids: HashMap<String, String>,
+    #[serde(default)]
+    patients: Vec<ElsaPatientManifest>,
+}
+
+/// A patient in the manifest, which groups the specimens of a case.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ElsaPatientManifest {
+    #[serde(default)]
+    ids: HashMap<String, String>,
+    #[serde(default)]
+    specimens: Vec<ElsaSpecimenManifest>,
+}
+
+/// A specimen in the manifest, which links the external identifiers of the specimen to the htsget
+/// id of its files.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ElsaSpecimenManifest {
+    #[serde(default)]
+    htsget_id: Option<String>,
+    #[serde(default)]
+    ids: HashMap<String, String>,
+}
+
+/// The external identifiers of the specimens of a release, such as `HG00097`, together with the
+/// htsget ids of the specimens that they identify.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpecimenIds {
+    ids: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// The external identifiers which resolve to files of one type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpecimenAliases {
+    aliases: HashMap<String, BTreeSet<String>>,
+    ambiguous: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl ElsaCaseManifest {
+    /// Get the external identifiers of the case.
+    pub fn ids(&self) -> &HashMap<String, String> {
+        &self.ids
+    }
+
+    /// Get the patients of the case.
+    pub fn patients(&self) -> &[ElsaPatientManifest] {
+        &self.patients
+    }
+}
+
+impl ElsaPatientManifest {
+    /// Get the external identifiers of the patient.
+    pub fn ids(&self) -> &HashMap<String, String> {
+        &self.ids
+    }
+
+    /// Get the specimens of the patient.
+    pub fn specimens(&self) -> &[ElsaSpecimenManifest] {
+        &self.specimens
+    }
+}
+
+impl SpecimenIds {
+    /// Collect the external identifiers of the specimens in the cases. Identifiers which are not
+    /// valid ids are left out, because they could not be used in a query.
+    pub fn from_cases(cases: &[ElsaCaseManifest]) -> Self {
+        let mut ids: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        let specimens = cases
+            .iter()
+            .flat_map(|case| &case.patients)
+            .flat_map(|patient| &patient.specimens);
+
+        for specimen in specimens {
+            let Some(htsget_id) = &specimen.htsget_id else {
+                continue;
+            };
+
+            for external_id in specimen.ids.values() {
+                if validate_identifier(external_id).is_err() {
+                    debug!(%external_id, "leaving out specimen id which is not a valid id");
+                    continue;
+                }
+
+                ids.entry(external_id.to_string())
+                    .or_default()
+                    .insert(htsget_id.to_string());
+            }
+        }
+
+        Self { ids }
+    }
+
+    /// Resolve the external identifiers to the files of one type, where `is_file` checks whether
+    /// an htsget id has a file of that type. An identifier which resolves to exactly one file
+    /// becomes an alias of that file, and an identifier which resolves to more than one file is
+    /// ambiguous. Identifiers which are already the htsget id of a file are not aliases.
+    pub fn aliases(&self, is_file: impl Fn(&str) -> bool) -> SpecimenAliases {
+        let mut aliases = SpecimenAliases::default();
+        for (external_id, htsget_ids) in &self.ids {
+            if is_file(external_id) {
+                continue;
+            }
+
+            let mut files: BTreeSet<String> = htsget_ids
+                .iter()
+                .filter(|htsget_id| is_file(htsget_id))
+                .cloned()
+                .collect();
+            if files.len() > 1 {
+                aliases.ambiguous.insert(external_id.to_string(), files);
+            } else if let Some(file) = files.pop_first() {
+                aliases
+                    .aliases
+                    .entry(file)
+                    .or_default()
+                    .insert(external_id.to_string());
+            }
+        }
+
+        aliases
+    }
+}
+
+impl SpecimenAliases {
+    /// Get the external identifiers which resolve to the file.
+    pub fn aliases(&self, htsget_id: &str) -> BTreeSet<String> {
+        self.aliases.get(htsget_id).cloned().unwrap_or_default()
+    }
+
+    /// Get the external identifiers which resolve to more than one file, together with the htsget
+    /// ids of those files.
+    pub fn ambiguous(&self) -> &BTreeMap<String, BTreeSet<String>> {
+        &self.ambiguous
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use serde_json::from_str;
+
+    use crate::specimen::{ElsaCaseManifest, SpecimenIds};
+
+    #[test]
+    fn specimen_aliases() {
+        let ids = SpecimenIds::from_cases(&example_cases());
+        let reads = ids.aliases(|id| ["A", "B", "C"].contains(&id));
+
+        assert_eq!(reads.aliases("A"), expected(&["HG00096"]));
+        assert_eq!(reads.aliases("B"), expected(&["NA12878"]));
+        assert_eq!(reads.aliases("C"), expected(&[]));
+        assert_eq!(
+            reads.ambiguous(),
+            &BTreeMap::from_iter([("HG00097".to_string(), expected(&["B", "C"]))])
+        );
+    }
+
+    #[test]
+    fn specimen_aliases_only_files_of_the_type() {
+        let ids = SpecimenIds::from_cases(&example_cases());
+        let variants = ids.aliases(|id| ["A", "B"].contains(&id));
+
+        assert_eq!(variants.aliases("B"), expected(&["HG00097", "NA12878"]));
+        assert!(variants.ambiguous().is_empty());
+    }
+
+    #[test]
+    fn specimen_aliases_leaves_out_invalid_ids() {
+        let ids = SpecimenIds::from_cases(&example_cases());
+
+        assert_eq!(
+            ids.aliases(|id| id == "A").aliases("A"),
+            expected(&["HG00096"])
+        );
+    }
+
+    fn example_cases() -> Vec<ElsaCaseManifest> {
+        from_str(
+            r#"[
+                {
+                    "ids": { "": "SINGLETONCHARLES" },
+                    "patients": [
+                        {
+                            "ids": { "": "CHARLES" },
+                            "specimens": [
+                                { "htsgetId": "A", "ids": { "": "HG00096", "other": "HG 00096" } }
+                            ]
+                        }
+                    ]
+                },
+                {
+                    "patients": [
+                        {
+                            "specimens": [
+                                { "htsgetId": "B", "ids": { "": "HG00097", "biobank": "NA12878" } },
+                                { "htsgetId": "C", "ids": { "": "HG00097" } },
+                                { "ids": { "": "HG00098" } }
+                            ]
+                        }
+                    ]
+                }
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    fn expected(ids: &[&str]) -> BTreeSet<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+}
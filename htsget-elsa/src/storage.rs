@@ -0,0 +1,488 @@
+use htsget_config::storage::s3::S3Storage;
+use htsget_config::storage::url::UrlStorage;
+use htsget_config::storage::Storage;
+use htsget_config::types::Format;
+use http::Uri;
+use serde::de::DeserializeOwned;
+use serde_json::{from_value, json, Value};
+
+use crate::Error::{InvalidManifest, UnsupportedManifestFeature};
+use crate::Result;
+
+/// The endpoint used to access Google Cloud Storage objects over HTTPS.
+pub const GCS_ENDPOINT: &str = "https://storage.googleapis.com";
+
+/// Convert a manifest url into the storage that holds the file and the object key of the file
+/// within that storage. The file ending of the format is stripped from the key.
+///
+/// The following schemes are supported:
+/// * `s3://bucket/key` uses S3 storage.
+/// * `gs://bucket/key` uses url storage with the Google Cloud Storage HTTPS endpoint.
+/// * `https://host/key` uses url storage with the host.
+///
+/// `file://` urls are rejected, because htsget can only serve local files from the data server of its
+/// own configuration, which cannot hold the files of a release.
+pub fn storage_from_url(url: &str, format: Format) -> Result<(Storage, String)> {
+    let (scheme, location) = url
+        .split_once("://")
+        .ok_or_else(|| InvalidManifest(format!("url does not have a scheme: `{url}`")))?;
+
+    let (storage, key) = match scheme.to_lowercase().as_str() {
+        "s3" => {
+            let (bucket, key) = split_bucket(location)?;
+            (
+                Storage::S3 {
+                    s3_storage: S3Storage::new(bucket.to_string(), None, false),
+                },
+                key.to_string(),
+            )
+        }
+        "gs" => {
+            let (bucket, key) = split_bucket(location)?;
+            (
+                url_storage(&format!("{GCS_ENDPOINT}/{bucket}/"))?,
+                key.to_string(),
+            )
+        }
+        "https" => https_location(url)?,
+        "file" => {
+            return Err(UnsupportedManifestFeature(format!(
+                "file urls cannot be served: `{url}`"
+            )))
+        }
+        scheme => {
+            return Err(UnsupportedManifestFeature(format!(
+                "unsupported manifest url scheme: `{scheme}`"
+            )))
+        }
+    };
+
+    if key.split('/').any(|segment| segment == "..") {
+        return Err(InvalidManifest(format!(
+            "object key must not contain `..`: `{key}`"
+        )));
+    }
+
+    Ok((storage, strip_file_ending(&key, format)))
+}
+
+/// Determine the format of a file from its declared format and the file ending of its url. If neither
+/// are present, the first of the allowed `formats` is used. An error is returned if the declared format
+/// contradicts the file ending, or if the format is not one of the allowed `formats`.
+pub fn infer_format(url: &str, declared: Option<Format>, formats: &[Format]) -> Result<Format> {
+    let url = url.to_lowercase();
+    let inferred = [Format::Bam, Format::Cram, Format::Vcf, Format::Bcf]
+        .into_iter()
+        .find(|format| url.ends_with(format.file_ending()));
+
+    let format = match (declared, inferred) {
+        (Some(declared), Some(inferred)) if declared != inferred => {
+            return Err(InvalidManifest(format!(
+                "declared format `{declared:?}` does not match the file ending of `{url}`"
+            )))
+        }
+        (Some(format), _) | (None, Some(format)) => format,
+        (None, None) => *formats
+            .first()
+            .ok_or_else(|| InvalidManifest("no formats are allowed".to_string()))?,
+    };
+
+    if !formats.contains(&format) {
+        return Err(InvalidManifest(format!(
+            "format `{format:?}` is not valid here, expected one of `{formats:?}`"
+        )));
+    }
+
+    Ok(format)
+}
+
+/// Check the reference url of a file. References are only used to decode CRAM files, but htsget
+/// tickets cannot carry a reference, so clients could not decode a CRAM file which needs an
+/// external reference. These files are rejected rather than served.
+pub fn check_reference_url(reference_url: &str, format: Format) -> Result<()> {
+    if format != Format::Cram {
+        return Err(InvalidManifest(format!(
+            "a reference url is only valid for CRAM files, found `{format:?}`"
+        )));
+    }
+
+    storage_from_url(reference_url, format)?;
+
+    Err(UnsupportedManifestFeature(format!(
+        "CRAM files which need an external reference are not supported: `{reference_url}`"
+    )))
+}
+
+/// Check that the explicit index url of a file is usable. htsget-rs locates the index of a file using
+/// the same storage and key as the file, so an index at any other location, including a different
+/// prefix or `.bai` rather than `.bam.bai` naming, cannot be served. An index at another location
+/// returns an unsupported manifest feature error, and an invalid index url returns an invalid manifest
+/// error.
+pub fn check_index_url(url: &str, index_url: &str, format: Format) -> Result<()> {
+    storage_from_url(index_url, format)?;
+
+    let url = lowercase_scheme(url);
+    let expected = format.fmt_index(url.strip_suffix(format.file_ending()).unwrap_or(&url));
+
+    if lowercase_scheme(index_url) != expected {
+        return Err(UnsupportedManifestFeature(format!(
+            "index must be located at `{expected}`, found `{index_url}`"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Convert the scheme of a url to lowercase.
+fn lowercase_scheme(url: &str) -> String {
+    match url.split_once("://") {
+        Some((scheme, location)) => format!("{}://{}", scheme.to_lowercase(), location),
+        None => url.to_string(),
+    }
+}
+
+/// Split the location of a bucket based url into the bucket and object key.
+fn split_bucket(location: &str) -> Result<(&str, &str)> {
+    let (bucket, key) = location.split_once('/').ok_or_else(|| {
+        InvalidManifest("could not split url into bucket and object key".to_string())
+    })?;
+
+    if bucket.is_empty() || key.is_empty() {
+        return Err(InvalidManifest("bucket or key is empty".to_string()));
+    }
+
+    Ok((bucket, key))
+}
+
+/// Get the url storage and object key of a HTTPS url.
+fn https_location(url: &str) -> Result<(Storage, String)> {
+    let uri = Uri::try_from(url).map_err(|err| InvalidManifest(err.to_string()))?;
+
+    if uri.query().is_some() {
+        return Err(InvalidManifest(
+            "https urls must not contain a query".to_string(),
+        ));
+    }
+
+    let authority = uri
+        .authority()
+        .filter(|authority| !authority.host().is_empty())
+        .ok_or_else(|| InvalidManifest("https url does not have a host".to_string()))?;
+
+    let key = uri.path().trim_start_matches('/');
+    if key.is_empty() {
+        return Err(InvalidManifest(
+            "https url does not have a path".to_string(),
+        ));
+    }
+
+    Ok((
+        url_storage(&format!("https://{authority}/"))?,
+        key.to_string(),
+    ))
+}
+
+/// Create url storage for the url. Storage is created from its serialized form so that htsget-config
+/// sets up the client used to fetch the data.
+fn url_storage(url: &str) -> Result<Storage> {
+    Ok(Storage::Url {
+        url_storage: storage_from_value::<UrlStorage>(json!({
+            "url": url,
+            "forward_headers": false,
+        }))?
+        .into(),
+    })
+}
+
+/// Deserialize a storage type from its serialized form.
+fn storage_from_value<T: DeserializeOwned>(value: Value) -> Result<T> {
+    from_value(value).map_err(|err| InvalidManifest(format!("failed to create storage: {}", err)))
+}
+
+/// Remove the file ending of the format from the key, if it is present.
+fn strip_file_ending(key: &str, format: Format) -> String {
+    key.strip_suffix(format.file_ending())
+        .unwrap_or(key)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use htsget_config::storage::Storage;
+    use htsget_config::types::Format;
+
+    use crate::storage::{check_index_url, check_reference_url, infer_format, storage_from_url};
+    use crate::Error::{InvalidManifest, UnsupportedManifestFeature};
+
+    #[test]
+    fn s3_url() {
+        let (storage, key) =
+            storage_from_url("s3://umccr-10g-data-dev/HG00097/HG00097.bam", Format::Bam).unwrap();
+
+        assert!(
+            matches!(storage, Storage::S3 { s3_storage } if s3_storage.bucket() == "umccr-10g-data-dev")
+        );
+        assert_eq!(key, "HG00097/HG00097");
+    }
+
+    #[test]
+    fn s3_url_uppercase() {
+        let (storage, key) =
+            storage_from_url("S3://umccr-10g-data-dev/HG00097/HG00097.bam", Format::Bam).unwrap();
+
+        assert!(matches!(storage, Storage::S3 { .. }));
+        assert_eq!(key, "HG00097/HG00097");
+    }
+
+    #[test]
+    fn s3_url_no_key() {
+        assert!(matches!(
+            storage_from_url("s3://umccr-10g-data-dev/", Format::Bam),
+            Err(InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn gs_url() {
+        let (storage, key) = storage_from_url(
+            "gs://umccr-10g-data-dev/HG00097/HG00097.hard-filtered.vcf.gz",
+            Format::Vcf,
+        )
+        .unwrap();
+
+        assert!(matches!(storage, Storage::Url { .. }));
+        assert_eq!(key, "HG00097/HG00097.hard-filtered");
+    }
+
+    #[test]
+    fn gs_url_no_bucket() {
+        assert!(matches!(
+            storage_from_url("gs:///HG00097/HG00097.bam", Format::Bam),
+            Err(InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn https_url() {
+        let (storage, key) =
+            storage_from_url("https://example.org/data/HG00097/HG00097.bam", Format::Bam).unwrap();
+
+        assert!(matches!(storage, Storage::Url { .. }));
+        assert_eq!(key, "data/HG00097/HG00097");
+    }
+
+    #[test]
+    fn https_url_with_port() {
+        let (storage, key) =
+            storage_from_url("https://example.org:8443/HG00097.bam", Format::Bam).unwrap();
+
+        assert!(matches!(storage, Storage::Url { .. }));
+        assert_eq!(key, "HG00097");
+    }
+
+    #[test]
+    fn https_url_no_path() {
+        assert!(matches!(
+            storage_from_url("https://example.org/", Format::Bam),
+            Err(InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn https_url_query() {
+        assert!(matches!(
+            storage_from_url("https://example.org/HG00097.bam?token=secret", Format::Bam),
+            Err(InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn http_url() {
+        assert!(matches!(
+            storage_from_url("http://example.org/HG00097.bam", Format::Bam),
+            Err(UnsupportedManifestFeature(_))
+        ));
+    }
+
+    #[test]
+    fn file_url() {
+        for url in [
+            "file:///data/HG00097/HG00097.bam",
+            "file://localhost/data/HG00097.bam",
+            "file:///data/../etc/HG00097.bam",
+            "FILE:///data/HG00097.bam",
+        ] {
+            assert!(matches!(
+                storage_from_url(url, Format::Bam),
+                Err(UnsupportedManifestFeature(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn url_parent_directory() {
+        assert!(matches!(
+            storage_from_url("https://example.org/data/../HG00097.bam", Format::Bam),
+            Err(InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn url_no_scheme() {
+        assert!(matches!(
+            storage_from_url("umccr-10g-data-dev/HG00097/HG00097.bam", Format::Bam),
+            Err(InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn index_url() {
+        assert!(check_index_url(
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam.bai",
+            Format::Bam
+        )
+        .is_ok());
+        assert!(check_index_url(
+            "S3://umccr-10g-data-dev/HG00097/HG00097.hard-filtered.vcf.gz",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.hard-filtered.vcf.gz.tbi",
+            Format::Vcf
+        )
+        .is_ok());
+        assert!(check_index_url(
+            "s3://umccr-10g-data-dev/HG00097/HG00097",
+            "s3://umccr-10g-data-dev/HG00097/HG00097.bam.bai",
+            Format::Bam
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn index_url_different_naming() {
+        assert!(matches!(
+            check_index_url(
+                "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+                "s3://umccr-10g-data-dev/HG00097/HG00097.bai",
+                Format::Bam
+            ),
+            Err(UnsupportedManifestFeature(_))
+        ));
+    }
+
+    #[test]
+    fn index_url_different_prefix() {
+        assert!(matches!(
+            check_index_url(
+                "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+                "s3://umccr-10g-data-dev/indexes/HG00097.bam.bai",
+                Format::Bam
+            ),
+            Err(UnsupportedManifestFeature(_))
+        ));
+    }
+
+    #[test]
+    fn index_url_invalid() {
+        assert!(matches!(
+            check_index_url(
+                "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
+                "s3://umccr-10g-data-dev",
+                Format::Bam
+            ),
+            Err(InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn infer_format_from_url() {
+        let reads = [Format::Bam, Format::Cram];
+        let variants = [Format::Vcf, Format::Bcf];
+
+        assert_eq!(
+            infer_format("s3://bucket/HG00097.bam", None, &reads).unwrap(),
+            Format::Bam
+        );
+        assert_eq!(
+            infer_format("s3://bucket/HG00097.cram", None, &reads).unwrap(),
+            Format::Cram
+        );
+        assert_eq!(
+            infer_format("s3://bucket/HG00097.vcf.gz", None, &variants).unwrap(),
+            Format::Vcf
+        );
+        assert_eq!(
+            infer_format("s3://bucket/HG00097.BCF", None, &variants).unwrap(),
+            Format::Bcf
+        );
+    }
+
+    #[test]
+    fn infer_format_default() {
+        assert_eq!(
+            infer_format("s3://bucket/HG00097", None, &[Format::Bam, Format::Cram]).unwrap(),
+            Format::Bam
+        );
+        assert_eq!(
+            infer_format(
+                "s3://bucket/HG00097",
+                Some(Format::Cram),
+                &[Format::Bam, Format::Cram]
+            )
+            .unwrap(),
+            Format::Cram
+        );
+    }
+
+    #[test]
+    fn infer_format_declared_matches() {
+        assert_eq!(
+            infer_format(
+                "s3://bucket/HG00097.bcf",
+                Some(Format::Bcf),
+                &[Format::Vcf, Format::Bcf]
+            )
+            .unwrap(),
+            Format::Bcf
+        );
+    }
+
+    #[test]
+    fn infer_format_declared_contradicts() {
+        assert!(matches!(
+            infer_format(
+                "s3://bucket/HG00097.cram",
+                Some(Format::Bam),
+                &[Format::Bam, Format::Cram]
+            ),
+            Err(InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn infer_format_not_allowed() {
+        assert!(matches!(
+            infer_format(
+                "s3://bucket/HG00097.vcf.gz",
+                None,
+                &[Format::Bam, Format::Cram]
+            ),
+            Err(InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn reference_url() {
+        assert!(matches!(
+            check_reference_url("s3://bucket/reference/GRCh38.fa", Format::Cram),
+            Err(UnsupportedManifestFeature(_))
+        ));
+        assert!(matches!(
+            check_reference_url("s3://bucket/reference/GRCh38.fa", Format::Bam),
+            Err(InvalidManifest(_))
+        ));
+        assert!(matches!(
+            check_reference_url("s3://bucket", Format::Cram),
+            Err(InvalidManifest(_))
+        ));
+    }
+}
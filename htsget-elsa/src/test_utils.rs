@@ -3,21 +3,32 @@ use std::fs;
 use std::future::Future;
 use std::path::{Path, PathBuf};
 
+use crate::contig::ContigAliases;
 use crate::elsa_endpoint::ENDPOINT_PATH;
 use aws_sdk_s3::Client;
 use htsget_config::resolver::ReferenceNames::List;
-use htsget_config::resolver::Resolver;
+use htsget_config::resolver::{ReferenceNames, Resolver};
 use htsget_config::storage;
 use htsget_config::types::{Format, Interval};
 use htsget_test::aws_mocks::with_s3_test_server_tmp;
-use wiremock::matchers::{method, path, query_param};
+use wiremock::matchers::{header, method, path, query_param};
 use wiremock::{Mock, MockServer, Request, ResponseTemplate, Times};
 
+/// A release which the mock Elsa server only returns for the `MEMBER_AUTHORIZATION`.
+pub const AUTHORIZED_RELEASE: &str = "R403";
+
+/// The authorization of a member of the `AUTHORIZED_RELEASE`.
+pub const MEMBER_AUTHORIZATION: &str = "Bearer member";
+
+/// The dataset that the example Elsa manifest declares.
+pub const EXAMPLE_DATASET_URI: &str = "urn:fdc:umccr.org:2022:dataset/10g";
+
 /// An example Elsa manifest file.
 pub fn example_elsa_manifest() -> String {
     r#"
         {
             "id": "R004",
+            "datasetUris": ["urn:fdc:umccr.org:2022:dataset/10g"],
             "reads": {
                 "30F9F3FED8F711ED8C35DBEF59E9F537": {
                     "url": "s3://umccr-10g-data-dev/HG00097/HG00097.bam",
@@ -127,8 +138,20 @@ pub fn write_example_manifest(manifest_path: &Path) {
     fs::write(manifest_path.join("R004"), example_elsa_manifest()).unwrap();
 }
 
-/// Runs a test with a mock Elsa server that accepts GET requests for the htsget manifest.
+/// Runs a test with a mock Elsa server that accepts GET requests for the htsget manifest. Requests
+/// for the `AUTHORIZED_RELEASE` are denied unless they have the `MEMBER_AUTHORIZATION`.
 pub async fn with_test_mocks<T, F, Fut>(test: F, expect_times: T)
+where
+    T: Into<Times>,
+    F: FnOnce(String, Client, reqwest::Client, PathBuf) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    with_manifest_mocks(example_elsa_manifest(), test, expect_times).await
+}
+
+/// Runs a test with a mock Elsa server like `with_test_mocks`, which returns the `manifest` for
+/// the R004 release instead of the example manifest.
+pub async fn with_manifest_mocks<T, F, Fut>(manifest: String, test: F, expect_times: T)
 where
     T: Into<Times>,
     F: FnOnce(String, Client, reqwest::Client, PathBuf) -> Fut,
@@ -144,7 +167,8 @@ where
             .respond_with(move |_: &Request| {
                 let manifest_path = base_path.join("elsa-data-tmp/htsget-manifests");
 
-                write_example_manifest(&manifest_path);
+                fs::create_dir_all(&manifest_path).unwrap();
+                fs::write(manifest_path.join("R004"), &manifest).unwrap();
 
                 ResponseTemplate::new(200).set_body_string(example_elsa_response())
             })
@@ -152,6 +176,27 @@ where
             .mount(&mock_server)
             .await;
 
+        let base_path = server_base_path.clone();
+        Mock::given(method("GET"))
+            .and(path(format!("{ENDPOINT_PATH}/{AUTHORIZED_RELEASE}")))
+            .and(query_param("type", "S3"))
+            .and(header("authorization", MEMBER_AUTHORIZATION))
+            .respond_with(move |_: &Request| {
+                let manifest_path = base_path.join("elsa-data-tmp/htsget-manifests");
+
+                write_example_manifest(&manifest_path);
+
+                ResponseTemplate::new(200).set_body_string(example_elsa_response())
+            })
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(format!("{ENDPOINT_PATH}/{AUTHORIZED_RELEASE}")))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&mock_server)
+            .await;
+
         test(
             mock_server.address().to_string(),
             client,
@@ -163,41 +208,75 @@ where
     .await;
 }
 
+/// The reference names that a restriction on the chromosome allows, including its aliases.
+pub fn reference_names(chromosome: &str) -> ReferenceNames {
+    List(HashSet::from_iter(
+        ContigAliases::default().aliases(chromosome),
+    ))
+}
+
 /// Check if the resolver correctly matches the 30F9F3FED8F711ED8C35DBEF59E9F537 reads example.
 pub fn is_reads_resolver_from_parts(resolver: &Resolver) -> bool {
     resolver.regex().to_string() == "^R004/30F9F3FED8F711ED8C35DBEF59E9F537$"
-        && resolver.substitution_string() == "HG00097/HG00097"
+        && is_reads_resolver(resolver)
+}
+
+/// Check if the resolver matches the id of a file in the example manifest, and the id of its
+/// specimen, but not the ids of other files.
+pub fn matches_file(resolver: &Resolver, id: &str, specimen_id: &str) -> bool {
+    let other_ids = [
+        "30F9F3FED8F711ED8C35DBEF59E9F537",
+        "30F9FFD4D8F711ED8C353BBCB8861211",
+        "HG00096",
+        "HG00097",
+    ];
+
+    resolver.regex().is_match(&format!("R004/{id}"))
+        && resolver.regex().is_match(&format!("R004/{specimen_id}"))
+        && other_ids
+            .into_iter()
+            .filter(|other_id| *other_id != id && *other_id != specimen_id)
+            .all(|other_id| !resolver.regex().is_match(&format!("R004/{other_id}")))
+}
+
+/// Check if the resolver has the storage and guard of the 30F9F3FED8F711ED8C35DBEF59E9F537 reads
+/// example.
+fn is_reads_resolver(resolver: &Resolver) -> bool {
+    resolver.substitution_string() == "HG00097/HG00097"
         && matches!(resolver.storage(), storage::Storage::S3 { s3_storage } if s3_storage.bucket() == "umccr-10g-data-dev")
         && resolver.allow_formats() == [Format::Bam]
-        && resolver.allow_reference_names() == &List(HashSet::from_iter(vec!["1".to_string()]))
+        && resolver.allow_reference_names() == &reference_names("1")
         && resolver.allow_interval() == Interval::new(Some(1), Some(10))
 }
 
 /// Check if the resolvers correct match the whole example manifest.
 pub fn is_manifest_resolvers(resolvers: Vec<Resolver>) -> bool {
     resolvers.iter().any(|resolver| {
-        resolver.regex().to_string() == "^R004/30F9FFD4D8F711ED8C353BBCB8861211$" &&
+        matches_file(resolver, "30F9FFD4D8F711ED8C353BBCB8861211", "HG00096") &&
             resolver.substitution_string() == "HG00096/HG00096" &&
             matches!(resolver.storage(), storage::Storage::S3 { s3_storage } if s3_storage.bucket() == "umccr-10g-data-dev") &&
             resolver.allow_formats() == [Format::Bam]
-            && resolver.allow_reference_names() == &List(HashSet::from_iter(vec!["2".to_string()]))
+            && resolver.allow_reference_names() == &reference_names("2")
             && resolver.allow_interval() == Interval::new(None, Some(10))
     }) &&
-    resolvers.iter().any(is_reads_resolver_from_parts) &&
     resolvers.iter().any(|resolver| {
-        resolver.regex().to_string() == "^R004/30F9FFD4D8F711ED8C353BBCB8861211$" &&
+        matches_file(resolver, "30F9F3FED8F711ED8C35DBEF59E9F537", "HG00097") &&
+            is_reads_resolver(resolver)
+    }) &&
+    resolvers.iter().any(|resolver| {
+        matches_file(resolver, "30F9FFD4D8F711ED8C353BBCB8861211", "HG00096") &&
             resolver.substitution_string() == "HG00096/HG00096.hard-filtered" &&
             matches!(resolver.storage(), storage::Storage::S3 { s3_storage } if s3_storage.bucket() == "umccr-10g-data-dev") &&
             resolver.allow_formats() == [Format::Vcf]
-            && resolver.allow_reference_names() == &List(HashSet::from_iter(vec!["4".to_string()]))
+            && resolver.allow_reference_names() == &reference_names("4")
             && resolver.allow_interval() == Interval::new(None, None)
     }) &&
     resolvers.iter().any(|resolver| {
-        resolver.regex().to_string() == "^R004/30F9F3FED8F711ED8C35DBEF59E9F537$" &&
+        matches_file(resolver, "30F9F3FED8F711ED8C35DBEF59E9F537", "HG00097") &&
             resolver.substitution_string() == "HG00097/HG00097.hard-filtered" &&
             matches!(resolver.storage(), storage::Storage::S3 { s3_storage } if s3_storage.bucket() == "umccr-10g-data-dev") &&
             resolver.allow_formats() == [Format::Vcf]
-            && resolver.allow_reference_names() == &List(HashSet::from_iter(vec!["3".to_string()]))
+            && resolver.allow_reference_names() == &reference_names("3")
             && resolver.allow_interval() == Interval::new(Some(10), None)
     })
 }
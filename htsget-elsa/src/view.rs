@@ -0,0 +1,144 @@
+use htsget_config::resolver::AllowGuard;
+use htsget_config::types::Class;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::Error::{InvalidManifest, UnsupportedManifestFeature};
+use crate::Result;
+
+/// A class of data in a file.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ElsaClassManifest {
+    Header,
+    Body,
+}
+
+impl From<ElsaClassManifest> for Class {
+    fn from(class: ElsaClassManifest) -> Self {
+        match class {
+            ElsaClassManifest::Header => Class::Header,
+            ElsaClassManifest::Body => Class::Body,
+        }
+    }
+}
+
+/// The view of a file that is shared, which restricts the classes that queries can request. If
+/// `classes` is not present, queries for any class are allowed.
+///
+/// htsget-rs returns whole records whatever fields or tags a query requests, so a view cannot
+/// remove fields or tags from a response. Manifests which set `fields`, `excludeFields` or `tags`
+/// are rejected, rather than sharing data that the view was meant to hold back.
+#[derive(Debug, Default, Clone, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ElsaViewManifest {
+    #[serde(default)]
+    classes: Option<Vec<ElsaClassManifest>>,
+    #[serde(default)]
+    fields: Option<Value>,
+    #[serde(default)]
+    exclude_fields: Option<Value>,
+    #[serde(default)]
+    tags: Option<Value>,
+}
+
+impl ElsaViewManifest {
+    /// Restrict the guard to the classes of the view.
+    pub fn restrict_guard(&self, guard: AllowGuard) -> Result<AllowGuard> {
+        for (name, value) in [
+            ("fields", &self.fields),
+            ("excludeFields", &self.exclude_fields),
+            ("tags", &self.tags),
+        ] {
+            if value.is_some() {
+                return Err(UnsupportedManifestFeature(format!(
+                    "`{name}` cannot be enforced, because htsget returns whole records"
+                )));
+            }
+        }
+
+        match &self.classes {
+            Some(classes) if classes.is_empty() => {
+                Err(InvalidManifest("`classes` must not be empty".to_string()))
+            }
+            Some(classes) => {
+                Ok(guard.with_allow_classes(classes.iter().copied().map(Class::from).collect()))
+            }
+            None => Ok(guard),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use htsget_config::resolver::{AllowGuard, IdResolver, Resolver};
+    use htsget_config::storage::s3::S3Storage;
+    use htsget_config::storage::Storage;
+    use htsget_config::types::{Class, Format, Query};
+    use serde_json::from_str;
+
+    use crate::view::ElsaViewManifest;
+    use crate::Error::{InvalidManifest, UnsupportedManifestFeature};
+
+    #[test]
+    fn view_default_allows_everything() {
+        let guard = ElsaViewManifest::default()
+            .restrict_guard(AllowGuard::default())
+            .unwrap();
+
+        assert_eq!(guard, AllowGuard::default());
+    }
+
+    #[test]
+    fn view_header_only() {
+        let guard = restrict(r#"{ "classes": ["header"] }"#).unwrap();
+        assert_eq!(guard.allow_classes(), [Class::Header]);
+
+        let resolver = resolver(guard);
+        let query = Query::new_with_default_request("id", Format::Bam);
+        assert!(resolver
+            .resolve_id(&query.clone().with_class(Class::Header))
+            .is_some());
+        assert!(resolver
+            .resolve_id(&query.with_class(Class::Body))
+            .is_none());
+    }
+
+    #[test]
+    fn view_unsupported() {
+        for view in [
+            r#"{ "fields": ["QNAME"] }"#,
+            r#"{ "excludeFields": ["QUAL"] }"#,
+            r#"{ "tags": ["RG"] }"#,
+            r#"{ "tags": [], "classes": ["header"] }"#,
+        ] {
+            assert!(matches!(restrict(view), Err(UnsupportedManifestFeature(_))));
+        }
+    }
+
+    #[test]
+    fn view_invalid() {
+        assert!(matches!(
+            restrict(r#"{ "classes": [] }"#),
+            Err(InvalidManifest(_))
+        ));
+    }
+
+    fn restrict(view: &str) -> crate::Result<AllowGuard> {
+        from_str::<ElsaViewManifest>(view)
+            .unwrap()
+            .restrict_guard(AllowGuard::default())
+    }
+
+    fn resolver(guard: AllowGuard) -> Resolver {
+        Resolver::new(
+            Storage::S3 {
+                s3_storage: S3Storage::new("bucket".to_string(), None, false),
+            },
+            "^id$",
+            "key",
+            guard,
+        )
+        .unwrap()
+    }
+}